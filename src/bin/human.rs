@@ -1,11 +1,15 @@
 use std::{io::stdin, process};
 use stratego::{
+    bitboard_loop,
     deployment::{heuristic::heuristic, Deployment},
     information::Information,
-    mcts::{Search, ISMCTS},
+    mcts::{Pondering, Search, ISMCTS},
     policy::{Policy, DEFAULT_WEIGHTS},
     select::Select,
-    stratego::{Flag, GameState, MoveList, Piece, Position, StrategoState},
+    stratego::{
+        square_notation, Army, Flag, GameRecord, Observation, Outcome, Piece, Position,
+        StrategoState,
+    },
     value::{evaluate, Value},
 };
 
@@ -14,13 +18,13 @@ fn main() {
 
     let deployment_type = select("deployment type", &["own", "heuristic", "dataset"]);
     let human = match deployment_type.as_str() {
-        "own" => custom_deployment(),
-        "heuristic" => heuristic(80, true),
-        "dataset" => Deployment::Dataset.get(),
+        "own" => custom_deployment(&color),
+        "heuristic" => heuristic(80, true, &Army::QUICK_BATTLE),
+        "dataset" => Deployment::Dataset.get(&Army::QUICK_BATTLE),
         _ => unreachable!(),
     };
 
-    let mut ismcts = ISMCTS::<false>::new(
+    let ismcts = ISMCTS::<false>::new(
         100_000,
         Value::SimulationOrderedCutoff(DEFAULT_WEIGHTS, 0.025, |pos: &mut StrategoState| {
             (evaluate(pos) / 750.0).tanh()
@@ -32,62 +36,123 @@ fn main() {
     );
 
     let deployments = if color == "red" {
-        deployment(&human, &ismcts.deployment())
+        deployment(&human, &ismcts.deployment(&Army::QUICK_BATTLE))
     } else {
-        deployment(&ismcts.deployment(), &human)
+        deployment(&ismcts.deployment(&Army::QUICK_BATTLE), &human)
     };
 
-    let pos = format!("{}/8/8/{} r", deployments.1, deployments.0);
-    let mut pos = StrategoState::from(&pos);
-
     let human_stm = if color == "red" { false } else { true };
 
+    let names = if human_stm {
+        ["ismcts", "human"]
+    } else {
+        ["human", "ismcts"]
+    };
+    let mut record = GameRecord::new(names[0], names[1], [deployments.0, deployments.1]);
+    let mut pos = record.start().unwrap();
+
+    // Agent thinks on while the human chooses a move
+    let mut ismcts = Pondering::new(ismcts);
+
     let mut capture = String::new();
     while !pos.game_over() {
         let moves = pos.gen();
 
-        if moves.len() == 0 {
-            pos.set_game_state(GameState::Loss);
-            break;
-        }
-
         if pos.stm() != human_stm {
-            let mov = ismcts.go(&mut pos);
+            let side = pos.stm() as usize;
+            let mov = ismcts.get().go(&pos.observe(side));
             println!("info move {}{}", capture, mov);
 
-            capture = make(&mut pos, &moves, &format!("{mov}"));
+            capture = make(&mut pos, &format!("{mov}"));
+            if !pos.game_over() {
+                ismcts.start(&pos.observe(side));
+            }
             continue;
         }
 
-        println!("{}", pos.anonymize((pos.stm() as usize) ^ 1));
-        println!("Choose move: [");
+        let observation = pos.observe(pos.stm() as usize);
+        print_board(&observation);
+        deductions(&observation);
+        println!("Choose move (or undo, log): [");
         moves.iter().for_each(|m| println!("  {m},"));
         println!("]");
 
         let mov = read();
-        capture = make(&mut pos, &moves, &mov);
+        if mov == "undo" {
+            // Take back the reply of the agent and our last move
+            if pos.ply() < 2 {
+                println!("error no move to undo");
+            } else {
+                pos.unmake();
+                pos.unmake();
+            }
+
+            capture = String::new();
+            continue;
+        }
+
+        if mov == "log" {
+            pos.events().iter().for_each(|event| println!("{event}"));
+            continue;
+        }
+
+        capture = make(&mut pos, &mov);
     }
 
-    println!("Game state: {:?}", pos.game_state());
+    let result = pos.result().unwrap();
+    println!("Game state: {:?} ({})", result.state, result.termination);
+
+    record.moves = pos.history();
+    record.result = Outcome::from(&pos);
+    record.termination = result.termination.to_string();
+    println!("{record}");
+}
+
+/// Colored board unless disabled by `NO_COLOR`, the plain board also shows the notation
+fn print_board(observation: &Observation) {
+    if std::env::var_os("NO_COLOR").is_some() {
+        println!("{observation}");
+    } else {
+        println!("{}", observation.board().ansi());
+    }
 }
 
-fn custom_deployment() -> String {
-    let (red, blue) = deployment(&heuristic(1000, false), &heuristic(1000, false));
+fn custom_deployment(color: &str) -> String {
+    let (red, blue) = deployment(
+        &heuristic(1000, false, &Army::QUICK_BATTLE),
+        &heuristic(1000, false, &Army::QUICK_BATTLE),
+    );
     println!("Example:\nRed: {}\nBlue: {}", red, blue);
 
     println!("Position: ");
     println!(
         "Flag {}\nSpy {}\nScout {}\nMiner {}\nGeneral {}\nMarshal {}\nBomb {}",
-        Position::SYMBOLS[8],
-        Position::SYMBOLS[9],
-        Position::SYMBOLS[10],
-        Position::SYMBOLS[11],
-        Position::SYMBOLS[12],
-        Position::SYMBOLS[13],
-        Position::SYMBOLS[15],
+        Position::symbol(1, Piece::FLAG),
+        Position::symbol(1, Piece::SPY),
+        Position::symbol(1, Piece::SCOUT),
+        Position::symbol(1, Piece::MINER),
+        Position::symbol(1, Piece::GENERAL),
+        Position::symbol(1, Piece::MARSHAL),
+        Position::symbol(1, Piece::BOMB),
     );
 
-    read()
+    loop {
+        let input = read();
+
+        // Validate deployment on an otherwise empty board
+        let (side, (red, blue)) = if color == "red" {
+            (0, deployment(&input, "8/8/8"))
+        } else {
+            (1, deployment("8/8/8", &input))
+        };
+
+        let army = Army::QUICK_BATTLE.len();
+        match format!("{}/8/8/{} r", blue, red).parse::<Position>() {
+            Ok(pos) if pos.get(side).count_ones() as usize == army => return input,
+            Ok(_) => println!("error deployment must contain {army} pieces"),
+            Err(err) => println!("error {err}"),
+        }
+    }
 }
 
 fn deployment(red: &str, blue: &str) -> (String, String) {
@@ -101,27 +166,57 @@ fn deployment(red: &str, blue: &str) -> (String, String) {
     )
 }
 
-fn make(pos: &mut StrategoState, moves: &MoveList, mov_str: &str) -> String {
-    for i in 0..moves.len() {
-        let mov = moves[i];
-
-        if format!("{mov}") != mov_str {
-            continue;
+/// Prints the narrowed ranks of the opponent's hidden pieces
+fn deductions(pos: &Observation) {
+    let stm = pos.side() ^ 1;
+    let info = pos.information();
+
+    // Ranks, which any hidden piece could have without deductions
+    let all = info
+        .available(stm)
+        .into_iter()
+        .chain(info.available_immovable(stm))
+        .fold(0u16, |mask, piece| mask | (1 << piece));
+
+    let deductions = info.deductions(stm);
+    let mut deduced = Vec::new();
+    let mut hidden = info.get(stm);
+    bitboard_loop!(hidden, sq, {
+        let candidates = deductions[sq as usize];
+
+        if candidates != all {
+            let ranks = (Piece::FLAG..=Piece::BOMB)
+                .filter(|&piece| (candidates & (1 << piece)) != 0)
+                .map(Piece::rank)
+                .collect::<Vec<_>>();
+
+            deduced.push(format!("{} {}", square_notation(sq), ranks.join("/")));
         }
+    });
 
-        let capture = if (mov.flag & Flag::CAPTURE) != 0 {
-            format!("{}x", Piece::rank(pos.board().piece(mov.to)))
-        } else {
-            String::new()
-        };
+    if !deduced.is_empty() {
+        println!("Deduced: {}", deduced.join(", "));
+    }
+}
 
-        pos.make(mov);
+fn make(pos: &mut StrategoState, mov_str: &str) -> String {
+    let mov = match pos.parse_move(mov_str) {
+        Ok(mov) => mov,
+        Err(err) => {
+            println!("error illegal move {mov_str}: {err}");
+            return String::new();
+        }
+    };
 
-        return capture;
-    }
+    let capture = if (mov.flag & Flag::CAPTURE) != 0 {
+        format!("{}x", Piece::rank(pos.board().piece(mov.to)))
+    } else {
+        String::new()
+    };
+
+    pos.make(mov);
 
-    println!("error illegal move {}", mov_str);
-    String::new()
+    capture
 }
 
 fn select(text: &str, options: &[&str]) -> String {
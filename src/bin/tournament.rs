@@ -1,7 +1,8 @@
+use std::{fs, thread};
 use stratego::{
     deployment::Deployment,
     information::Information,
-    mcts::{ISMCTS, PIMC},
+    mcts::{Parallelism, ISMCTS, PIMC},
     policy::{Policy, DEFAULT_WEIGHTS},
     random::UniformRandom,
     select::Select,
@@ -17,7 +18,7 @@ fn main() {
 
     let mut tournament = Tournament::new(150);
 
-    let one = ISMCTS::new(
+    let mut one = ISMCTS::new(
         10_000,
         Value::SimulationOrderedCutoff(DEFAULT_WEIGHTS, 0.025, |pos: &mut StrategoState| {
             (evaluate(pos) / 750.0).tanh()
@@ -27,7 +28,7 @@ fn main() {
         Deployment::Dataset,
         Information::Random,
     );
-    let two = ISMCTS::new(
+    let mut two = ISMCTS::new(
         10_000,
         Value::SimulationOrderedCutoff(DEFAULT_WEIGHTS, 0.025, |pos: &mut StrategoState| {
             (evaluate(pos) / 750.0).tanh()
@@ -37,7 +38,7 @@ fn main() {
         Deployment::Dataset,
         Information::Random,
     );
-    let three = PIMC::new(
+    let mut three = PIMC::new(
         10,
         1_000,
         Value::SimulationOrderedCutoff(DEFAULT_WEIGHTS, 0.025, |pos: &mut StrategoState| {
@@ -49,6 +50,13 @@ fn main() {
         Information::Random,
     );
 
+    // Games are played one after another, so each search can use all cores
+    let threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    one.set_parallelism(Parallelism::Tree(threads));
+    two.set_parallelism(Parallelism::Tree(threads));
+    three.set_parallelism(Parallelism::Tree(threads));
+    three.set_enumerate(true);
+
     tournament.add("soismcts", Algorithm::SOISMCTS(one), false);
     tournament.add("moismcts", Algorithm::MOISMCTS(two), false);
     tournament.add("pimc", Algorithm::PIMC(three), false);
@@ -59,4 +67,7 @@ fn main() {
     );
 
     tournament.run(50);
+
+    let records = tournament.records().iter().map(|record| record.to_string());
+    fs::write("tournament.games", records.collect::<Vec<_>>().join("\n")).unwrap();
 }
@@ -1,7 +1,10 @@
-use crate::stratego::Position;
+use crate::stratego::{Army, Position};
 use heuristic::heuristic;
-use rand::{rng, seq::IndexedRandom, Rng};
-use std::collections::HashSet;
+use rand::{
+    rng,
+    seq::{index, IndexedRandom},
+    Rng,
+};
 
 pub mod heuristic;
 #[cfg(feature = "tch")]
@@ -21,80 +24,100 @@ pub enum Deployment {
 }
 
 impl Deployment {
-    pub fn get(&self) -> String {
+    /// Deployment of `army`, only random deployments are available for all armies
+    pub fn get(&self, army: &Army) -> String {
         match self {
-            Deployment::Random => random(),
-            Deployment::Heuristic(attempts, min) => heuristic(*attempts, *min),
-            Deployment::Dataset => dataset(),
+            Deployment::Random => random(army),
+            Deployment::Heuristic(attempts, min) => heuristic(*attempts, *min, army),
+            Deployment::Dataset if *army == Army::QUICK_BATTLE => dataset(),
             #[cfg(feature = "tch")]
-            Deployment::Network(net, attempts) => net.get(*attempts),
+            Deployment::Network(net, attempts) if *army == Army::QUICK_BATTLE => net.get(*attempts),
+            _ => random(army),
         }
     }
 }
 
-pub fn random() -> String {
-    const PIECES: [char; 10] = [
-        Position::SYMBOLS[8],
-        Position::SYMBOLS[9],
-        Position::SYMBOLS[10],
-        Position::SYMBOLS[10],
-        Position::SYMBOLS[11],
-        Position::SYMBOLS[11],
-        Position::SYMBOLS[12],
-        Position::SYMBOLS[13],
-        Position::SYMBOLS[15],
-        Position::SYMBOLS[15],
-    ];
+pub fn random(army: &Army) -> String {
+    let variant = army.variant();
+    let files = variant.files();
 
-    let mut deployment = [' '; 24];
+    let mut deployment = vec![' '; files * variant.deployment_ranks()];
 
     let mut rng = rng();
-    let mut indices = HashSet::new();
-    while indices.len() < 10 {
-        indices.insert(rng.random_range(..deployment.len()));
-    }
+    let pieces = army.pieces();
+    let indices = index::sample(&mut rng, deployment.len(), pieces.len());
 
     indices
         .iter()
-        .zip(PIECES.iter())
-        .for_each(|(i, piece)| deployment[*i] = *piece);
+        .zip(pieces.iter())
+        .for_each(|(i, &piece)| deployment[i] = Position::symbol(1, piece));
 
-    let mut board = String::new();
-    for rank in deployment.chunks(8) {
-        let mut last_piece = 0;
+    deployment
+        .chunks(files)
+        .map(rank_notation)
+        .collect::<Vec<_>>()
+        .join("/")
+}
 
-        for cell in rank {
-            if *cell == ' ' {
-                last_piece += 1;
-                continue;
+/// Deployment mirrored between the left and right side of the board
+pub fn mirror(deployment: &str) -> String {
+    deployment
+        .split('/')
+        .map(|rank| {
+            let mut cells = Vec::new();
+            let mut empty = 0;
+
+            // Empty squares can span multiple digits, e.g. `10`
+            for c in rank.chars() {
+                if let Some(digit) = c.to_digit(10) {
+                    empty = empty * 10 + digit as usize;
+                    continue;
+                }
+
+                cells.extend(std::iter::repeat_n(' ', empty));
+                cells.push(c);
+                empty = 0;
             }
+            cells.extend(std::iter::repeat_n(' ', empty));
 
-            if last_piece != 0 {
-                board.push(char::from(last_piece + b'0'));
-            }
+            cells.reverse();
+            rank_notation(&cells)
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
 
-            board.push(*cell);
-            last_piece = 0;
+/// Notation of a rank, whose empty squares are spaces
+fn rank_notation(cells: &[char]) -> String {
+    let mut rank = String::new();
+    let mut last_piece = 0;
+
+    for cell in cells {
+        if *cell == ' ' {
+            last_piece += 1;
+            continue;
         }
 
         if last_piece != 0 {
-            board.push(char::from(last_piece + b'0'));
+            rank.push_str(&last_piece.to_string());
         }
 
-        board.push('/');
+        rank.push(*cell);
+        last_piece = 0;
     }
 
-    let mut chars = board.chars();
-    chars.next_back();
+    if last_piece != 0 {
+        rank.push_str(&last_piece.to_string());
+    }
 
-    chars.as_str().to_string()
+    rank
 }
 
 fn dataset() -> String {
     const DEPLOYMENTS: [&str; 12] = [
         "1c6/2d3mc/d1sgbfb1",
         "3bfbc1/1cd1m3/2sgd3",
-        "fb1c4/bg4c1/1dsgd3",
+        "fb1c4/bm4c1/1dsgd3",
         "1c6/s2cm3/g1d1dfbb",
         "7c/d1fbg3/cbmd1s2",
         "4mfbc/c4bds/1d2g3",
@@ -110,11 +133,7 @@ fn dataset() -> String {
     let mut deployment = DEPLOYMENTS.choose(&mut rng).unwrap().to_string();
 
     if rng.random() {
-        deployment = deployment
-            .split('/')
-            .map(|s| s.chars().rev().collect::<String>())
-            .collect::<Vec<_>>()
-            .join("/");
+        deployment = mirror(&deployment);
     }
 
     deployment
@@ -1,17 +1,25 @@
 use super::random;
 use crate::{
     bitboard_loop,
-    stratego::{Piece, Position},
+    stratego::{Army, Piece, Position, Variant},
 };
 
-pub fn heuristic(attempts: usize, min: bool) -> String {
-    if min {
+/// Best of `attempts` random deployments or the first one scoring more than `attempts`
+///
+/// The evaluation is only defined for the Quick Battle board, but works with any army on it.
+/// The minimum score is only meaningful for the Quick Battle army.
+pub fn heuristic(attempts: usize, min: bool, army: &Army) -> String {
+    if army.variant() != Variant::QuickBattle {
+        return random(army);
+    }
+
+    if min && *army == Army::QUICK_BATTLE {
         return heuristic_min(attempts as isize);
     }
 
     let (deployment, _) = (0..attempts)
         .map(|_| {
-            let deployment = random();
+            let deployment = random(army);
             let score = evaluate(&deployment);
 
             (deployment, score)
@@ -24,7 +32,7 @@ pub fn heuristic(attempts: usize, min: bool) -> String {
 
 pub fn heuristic_min(min: isize) -> String {
     loop {
-        let deployment = random();
+        let deployment = random(&Army::QUICK_BATTLE);
         let score = evaluate(&deployment);
 
         if score > min {
@@ -37,8 +45,10 @@ pub fn evaluate(deployment: &str) -> isize {
     evaluate_bb(bitboards(&deployment))
 }
 
-pub fn evaluate_bb(bb: [u64; 10]) -> isize {
-    let occ = bb[0] | Position::LAKES;
+pub fn evaluate_bb(bb: [u64; 15]) -> isize {
+    const LAKES: u64 = 0x2424000000;
+
+    let occ = bb[0] | LAKES;
 
     flag_placement(bb[Piece::FLAG])
         + flag_protection(occ, bb[Piece::FLAG], bb[Piece::BOMB], bb[Piece::MARSHAL])
@@ -141,19 +151,8 @@ fn front_row(scout: u64, miner: u64, general: u64) -> isize {
     score
 }
 
-fn bitboards(deployment: &str) -> [u64; 10] {
-    const SYMBOLS: [char; 8] = [
-        Position::SYMBOLS[8],
-        Position::SYMBOLS[9],
-        Position::SYMBOLS[10],
-        Position::SYMBOLS[11],
-        Position::SYMBOLS[12],
-        Position::SYMBOLS[13],
-        Position::SYMBOLS[14],
-        Position::SYMBOLS[15],
-    ];
-
-    let mut bb = [0u64; 10];
+fn bitboards(deployment: &str) -> [u64; 15] {
+    let mut bb = [0u64; 15];
 
     let (mut file, mut rank) = (0, 0);
     for c in deployment.chars() {
@@ -162,10 +161,12 @@ fn bitboards(deployment: &str) -> [u64; 10] {
             '/' => (file, rank) = (0, rank + 1),
             _ => {
                 let sq = (file + rank * 8) as usize;
-                let piece = SYMBOLS.iter().position(|&symbol| symbol == c).unwrap();
+                let piece = (Piece::FLAG..=Piece::BOMB)
+                    .find(|&piece| Position::symbol(1, piece) == c)
+                    .unwrap();
 
                 bb[0] |= 1u64 << sq;
-                bb[piece + 2] |= 1u64 << sq;
+                bb[piece] |= 1u64 << sq;
 
                 file += 1;
             }
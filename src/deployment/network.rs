@@ -1,5 +1,5 @@
 use super::random;
-use crate::stratego::Position;
+use crate::stratego::{Army, Piece, Position};
 use ordered_float::OrderedFloat;
 use tch::{
     nn::{self},
@@ -29,7 +29,7 @@ impl Network {
     pub fn get(&self, attempts: usize) -> String {
         let (deployment, _) = (0..attempts)
             .map(|_| {
-                let deployment = random();
+                let deployment = random(&Army::QUICK_BATTLE);
                 let data = Network::tensor(&deployment);
 
                 let mut scores = [0f32; 1];
@@ -57,13 +57,13 @@ impl Network {
 
     pub fn tensor(deployment: &str) -> Tensor {
         const SYMBOLS: [char; 7] = [
-            Position::SYMBOLS[8],
-            Position::SYMBOLS[9],
-            Position::SYMBOLS[10],
-            Position::SYMBOLS[11],
-            Position::SYMBOLS[12],
-            Position::SYMBOLS[13],
-            Position::SYMBOLS[15],
+            Position::symbol(1, Piece::FLAG),
+            Position::symbol(1, Piece::SPY),
+            Position::symbol(1, Piece::SCOUT),
+            Position::symbol(1, Piece::MINER),
+            Position::symbol(1, Piece::GENERAL),
+            Position::symbol(1, Piece::MARSHAL),
+            Position::symbol(1, Piece::BOMB),
         ];
 
         let mut data = [0f32; 24 * 7];
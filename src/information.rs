@@ -1,9 +1,17 @@
-use crate::{stratego::StrategoState, value::evaluate};
+use crate::{
+    bitboard_loop,
+    stratego::{rank, Beliefs, Event, EventKind, Piece, StrategoState},
+    value::evaluate,
+};
 use ordered_float::OrderedFloat;
+use rand::distr::{weighted::WeightedIndex, Distribution};
 
 pub enum Information {
     Random,
     Heuristic(usize),
+    /// Samples determinizations, which are weighted by the likelihood of the opponent's
+    /// moves under the behaviour model and of the pieces, which stayed still
+    Weighted(usize),
 }
 
 impl Information {
@@ -23,6 +31,148 @@ impl Information {
 
                 det
             }
+            Information::Weighted(samples) => weighted(pos, *samples),
         }
     }
 }
+
+/// Likelihood of an event of a hidden piece given its rank
+///
+/// Pieces advancing towards the opponent are more likely of a high rank.
+pub fn behaviour(event: &Event, piece: usize) -> f32 {
+    let EventKind::Moved { from, to } = event.kind else {
+        return 1.0;
+    };
+
+    let forward = if event.side == 0 {
+        rank(to) > rank(from)
+    } else {
+        rank(to) < rank(from)
+    };
+
+    match piece {
+        Piece::SPY..=Piece::MARSHAL if forward => {
+            1.0 + (piece - Piece::SPY) as f32 / (Piece::MARSHAL - Piece::SPY) as f32 / 2.0
+        }
+        _ => 1.0,
+    }
+}
+
+/// Probability, that a movable piece stays still during a move of its side
+const STAY: f32 = 0.97;
+
+/// Likelihood of a piece, which stayed still during `moves` moves of its side, given its rank
+///
+/// Bombs and the flag never move, while each move without it makes a movable piece less likely.
+fn unmoved(piece: usize, moves: usize) -> f32 {
+    match piece {
+        Piece::FLAG | Piece::BOMB => 1.0,
+        _ => STAY.powi(moves as i32),
+    }
+}
+
+/// Likelihood of the behaviour of each hidden piece of the opponent of the side to move
+/// given its rank
+///
+/// Moves are rated by `behaviour` and pieces, which never moved, by the moves of the
+/// opponent, during which they stayed still.
+pub fn likelihoods(pos: &StrategoState) -> Beliefs {
+    let stm = pos.stm() as usize;
+    let info = pos.information();
+    let mut likelihoods = pos.likelihoods(stm, behaviour);
+
+    // Opponent has made the last move
+    let moves = pos.ply().div_ceil(2);
+
+    let mut bb = info.get(stm ^ 1) & info.initial(stm ^ 1);
+    bitboard_loop!(bb, sq, {
+        for (piece, likelihood) in likelihoods[sq as usize].iter_mut().enumerate() {
+            *likelihood *= unmoved(piece, moves);
+        }
+    });
+
+    likelihoods
+}
+
+/// Draws one of `samples` determinizations proportional to the likelihood of the
+/// opponent's hidden pieces
+fn weighted(pos: &StrategoState, samples: usize) -> StrategoState {
+    let stm = pos.stm() as usize;
+    let board = pos.board();
+    let hidden = board.get(Piece::UNKNOWN) & board.get(stm ^ 1);
+
+    let likelihoods = likelihoods(pos);
+
+    let mut dets: Vec<_> = (0..samples.max(1)).map(|_| pos.determination()).collect();
+    let weights = dets.iter().map(|det| {
+        let mut weight = 1.0;
+
+        let mut bb = hidden;
+        bitboard_loop!(
+            bb,
+            sq,
+            weight *= likelihoods[sq as usize][det.board().piece(sq)]
+        );
+
+        weight
+    });
+
+    let index = WeightedIndex::new(weights.collect::<Vec<f32>>())
+        .map_or(0, |dist| dist.sample(&mut rand::rng()));
+
+    dets.swap_remove(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stratego::Flag;
+    use rand::Rng;
+
+    /// Probability of a bomb on `sq` from the view of the side to move
+    fn bomb(pos: &StrategoState, sq: u8) -> f32 {
+        let prior = pos.beliefs(pos.stm() as usize)[sq as usize];
+        let likelihood = likelihoods(pos)[sq as usize];
+
+        let posterior: Vec<_> = prior.iter().zip(likelihood).map(|(p, l)| p * l).collect();
+        posterior[Piece::BOMB] / posterior.iter().sum::<f32>()
+    }
+
+    #[test]
+    fn long_unmoved_pieces_are_likely_bombs() {
+        let mut rng = rand::rng();
+        let mut pos = StrategoState::from("d2f4/bbg4c/1m3dsc/8/8/BD3M1G/F5SD/1BCC4 r");
+
+        // Red observes blue after its first move
+        pos.make(pos.gen()[0]);
+        pos.make(pos.gen()[0]);
+        let early = pos.clone();
+
+        while pos.ply() < 60 {
+            let quiet: Vec<_> = pos
+                .gen()
+                .iter()
+                .filter(|mov| (mov.flag & Flag::CAPTURE) == 0)
+                .collect();
+            if quiet.is_empty() {
+                break;
+            }
+
+            pos.make(quiet[rng.random_range(0..quiet.len())]);
+        }
+
+        if pos.stm() {
+            pos.unmake();
+        }
+        assert!(pos.ply() > 20);
+
+        let info = pos.information();
+        let unmoved = info.get(1) & info.initial(1);
+        let moved = info.get(1) & !info.initial(1);
+        assert!(unmoved != 0 && moved != 0);
+
+        let sq = unmoved.trailing_zeros() as u8;
+        assert!(bomb(&pos, sq) > bomb(&early, sq));
+        assert!(bomb(&pos, sq) > bomb(&pos, moved.trailing_zeros() as u8));
+    }
+}
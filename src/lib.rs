@@ -1,6 +1,6 @@
-use mcts::{Search, ISMCTS, MCTS, PIMC};
+use mcts::{Limits, Ponder, Search, Stop, ISMCTS, MCTS, PIMC};
 use random::UniformRandom;
-use stratego::{Move, StrategoState};
+use stratego::{Army, Move, Observation};
 
 pub mod deployment;
 pub mod information;
@@ -21,9 +21,12 @@ pub enum Algorithm {
 }
 
 impl Algorithm {
-    pub fn go(&mut self, pos: &StrategoState) -> Move {
+    /// Best move in the game observed by the side to move
+    pub fn go(&mut self, pos: &Observation) -> Move {
         match self {
-            Algorithm::MCTS(a) => a.go(pos),
+            // Perfect information search can't move pieces of unknown rank, so it searches a
+            // random assignment of them, which is the game itself for cheating engines
+            Algorithm::MCTS(a) => a.go(&pos.determination()),
             Algorithm::PIMC(a) => a.go(pos),
             Algorithm::SOISMCTS(a) => a.go(pos),
             Algorithm::MOISMCTS(a) => a.go(pos),
@@ -31,13 +34,55 @@ impl Algorithm {
         }
     }
 
-    pub fn deployment(&mut self) -> String {
+    /// Limits of the search, if the algorithm searches at all
+    pub fn limits(&self) -> Option<Limits> {
         match self {
-            Algorithm::MCTS(a) => a.deployment(),
-            Algorithm::PIMC(a) => a.deployment(),
-            Algorithm::SOISMCTS(a) => a.deployment(),
-            Algorithm::MOISMCTS(a) => a.deployment(),
-            Algorithm::Random(a) => a.deployment(),
+            Algorithm::MCTS(a) => Some(a.limits()),
+            Algorithm::PIMC(a) => Some(a.limits()),
+            Algorithm::SOISMCTS(a) => Some(a.limits()),
+            Algorithm::MOISMCTS(a) => Some(a.limits()),
+            Algorithm::Random(_) => None,
+        }
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        match self {
+            Algorithm::MCTS(a) => a.set_limits(limits),
+            Algorithm::PIMC(a) => a.set_limits(limits),
+            Algorithm::SOISMCTS(a) => a.set_limits(limits),
+            Algorithm::MOISMCTS(a) => a.set_limits(limits),
+            Algorithm::Random(_) => {}
+        }
+    }
+
+    pub fn deployment(&mut self, army: &Army) -> String {
+        match self {
+            Algorithm::MCTS(a) => a.deployment(army),
+            Algorithm::PIMC(a) => a.deployment(army),
+            Algorithm::SOISMCTS(a) => a.deployment(army),
+            Algorithm::MOISMCTS(a) => a.deployment(army),
+            Algorithm::Random(a) => a.deployment(army),
+        }
+    }
+}
+
+impl Ponder for Algorithm {
+    fn stop_handle(&self) -> Stop {
+        match self {
+            Algorithm::MCTS(a) => a.stop_handle(),
+            Algorithm::PIMC(a) => a.stop_handle(),
+            Algorithm::SOISMCTS(a) => a.stop_handle(),
+            Algorithm::MOISMCTS(a) => a.stop_handle(),
+            Algorithm::Random(_) => Stop::default(),
+        }
+    }
+
+    /// Only ISMCTS ponders, the other algorithms return immediately
+    fn ponder(&mut self, pos: &Observation) {
+        match self {
+            Algorithm::SOISMCTS(a) => a.ponder(pos),
+            Algorithm::MOISMCTS(a) => a.ponder(pos),
+            _ => {}
         }
     }
 }
@@ -1,7 +1,7 @@
 use std::{io::stdin, process, time::Instant};
 use stratego::information::Information;
 use stratego::mcts::ISMCTS;
-use stratego::stratego::{MoveList, StrategoState};
+use stratego::stratego::{divide, perft, perft_checked, StrategoState, PERFT_SUITE};
 use stratego::{deployment::Deployment, policy::Policy, select::Select, value::Value};
 
 fn main() {
@@ -9,8 +9,7 @@ fn main() {
 
     let game_moves = [];
     for mov in game_moves {
-        let pos_gen = pos.gen();
-        make_move(&mut pos, &pos_gen, mov);
+        make_move(&mut pos, mov);
 
         println!("{}", mov);
         println!("{}", pos);
@@ -41,43 +40,57 @@ fn main() {
         match first {
             "quit" => std::process::exit(0),
             "d" => println!("{pos}"),
+            "position" => match commands[1..].join(" ").parse() {
+                Ok(new) => pos = new,
+                Err(err) => println!("error {err}"),
+            },
             "moves" => moves.iter().for_each(|m| println!("{m}")),
-            "move" => make_move(&mut pos, &moves, commands[1]),
-            "perft" => run_perft(&pos, 6),
+            "move" => make_move(&mut pos, commands[1]),
+            "perft" => match commands.get(1) {
+                Some(&"suite") => run_suite(),
+                depth => run_perft(&pos, depth.and_then(|d| d.parse().ok()).unwrap_or(6)),
+            },
+            "divide" => run_divide(&pos, depth(&commands)),
+            "check" => match perft_checked(&mut pos.clone(), depth(&commands)) {
+                Ok(nodes) => println!("check nodes {nodes}"),
+                Err(err) => println!("error {err}"),
+            },
             "go" => {
-                let annonym = pos.anonymize(pos.stm() as usize ^ 1);
+                let observation = pos.observe(pos.stm() as usize);
 
-                println!("info ismcts move {}", ismcts.go(&annonym));
+                println!("info ismcts move {}", ismcts.go(&observation));
             }
-            "annonym" => println!("{}", pos.anonymize(pos.stm() as usize ^ 1)),
+            "tree" => {
+                let depth = commands.get(2).and_then(|d| d.parse().ok()).unwrap_or(2);
+                let visits = commands.get(3).and_then(|v| v.parse().ok()).unwrap_or(0);
+
+                match commands.get(1) {
+                    Some(&"json") => print!("{}", ismcts.root().json(depth, visits)),
+                    _ => print!("{}", ismcts.root().dot(depth, visits)),
+                }
+            }
+            "annonym" => println!("{}", pos.observe(pos.stm() as usize)),
             "deter" => {
-                let annonym = pos.anonymize(pos.stm() as usize ^ 1);
-                println!("{}", annonym.determination());
+                let observation = pos.observe(pos.stm() as usize);
+                println!("{}", observation.determination());
             }
             _ => {}
         }
     }
 }
 
-fn make_move(pos: &mut StrategoState, moves: &MoveList, mov_str: &str) {
-    for i in 0..moves.len() {
-        let mov = moves[i];
-
-        if format!("{mov}") == mov_str {
-            pos.make(mov);
-
-            return;
-        }
+fn make_move(pos: &mut StrategoState, mov_str: &str) {
+    match pos.parse_move(mov_str) {
+        Ok(mov) => pos.make(mov),
+        Err(err) => println!("error illegal move {mov_str}: {err}"),
     }
-
-    println!("error illegal move {}", mov_str);
 }
 
 fn run_perft(pos: &StrategoState, depth: usize) {
-    let pos = pos.clone();
+    let mut pos = pos.clone();
 
     let now = Instant::now();
-    let nodes = perft(pos, depth);
+    let nodes = perft(&mut pos, depth);
 
     let time = now.elapsed().as_micros();
     println!(
@@ -87,21 +100,40 @@ fn run_perft(pos: &StrategoState, depth: usize) {
     );
 }
 
-fn perft(pos: StrategoState, depth: usize) -> usize {
-    if depth == 0 {
-        return 1;
-    }
+fn run_divide(pos: &StrategoState, depth: usize) {
+    let mut pos = pos.clone();
 
-    let mut nodes = 0;
+    let moves = divide(&mut pos, depth);
+    moves
+        .iter()
+        .for_each(|(mov, nodes)| println!("{mov}: {nodes}"));
 
-    for mov in pos.gen().iter() {
-        let mut new_pos = pos.clone();
+    let nodes = moves.iter().map(|(_, nodes)| nodes).sum::<usize>();
+    println!("divide {depth} moves {} nodes {nodes}", moves.len());
+}
 
-        new_pos.make(mov);
-        let child_nodes = perft(new_pos, depth - 1);
+fn run_suite() {
+    let mut failed = 0;
 
-        nodes += child_nodes;
+    for case in PERFT_SUITE {
+        let mut pos = case.state();
+
+        let result = match perft_checked(&mut pos, case.depth) {
+            Ok(nodes) if nodes == case.nodes => "ok".to_string(),
+            Ok(nodes) => format!("failed nodes {nodes} expected {}", case.nodes),
+            Err(err) => format!("failed {err}"),
+        };
+
+        if result != "ok" {
+            failed += 1;
+        }
+
+        println!("info suite {} depth {} {result}", case.name, case.depth);
     }
 
-    nodes
+    println!("suite failed {failed}/{}", PERFT_SUITE.len());
+}
+
+fn depth(commands: &[&str]) -> usize {
+    commands.get(1).and_then(|d| d.parse().ok()).unwrap_or(1)
 }
@@ -1,18 +1,38 @@
+mod export;
 mod ismcts;
 mod iteration;
+mod limits;
 mod mcts;
 mod node;
 mod pimc;
+mod ponder;
 
 pub use ismcts::ISMCTS;
+pub use limits::{Clock, Limits, Stop, TimeManager};
 pub use mcts::MCTS;
 pub use node::{Node, NodeStats};
 pub use pimc::PIMC;
+pub use ponder::{Ponder, Pondering};
 use rand::distr::weighted::WeightedIndex;
 
-use crate::stratego::{Move, StrategoState};
+use crate::stratego::{Army, Move, StrategoState};
 use std::sync::Arc;
 
+/// Distribution of the iterations of a search over threads
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Parallelism {
+    /// Threads share one tree, in which nodes selected by other threads have a virtual loss
+    Tree(usize),
+    /// Each thread searches its own tree, whose statistics of the root children are merged
+    Root(usize),
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Parallelism::Tree(1)
+    }
+}
+
 pub trait Search {
     fn select(&self, node: &Node, moves: &[Move]) -> Option<Arc<Node>>;
 
@@ -20,7 +40,7 @@ pub trait Search {
 
     fn policy(&self, pos: &StrategoState, moves: &Vec<Move>) -> WeightedIndex<f32>;
 
-    fn deployment(&self) -> String;
+    fn deployment(&self, army: &Army) -> String;
 
     fn information(&self, pos: &StrategoState) -> StrategoState;
 }
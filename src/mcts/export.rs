@@ -0,0 +1,128 @@
+use super::Node;
+use std::sync::Arc;
+
+impl Node {
+    /// Graphviz DOT graph of the tree below the node up to `depth` plies, which only contains
+    /// nodes with at least `visits` visits
+    ///
+    /// Children are ordered by their visits and terminal nodes are filled.
+    pub fn dot(&self, depth: usize, visits: usize) -> String {
+        let mut dot =
+            String::from("digraph tree {\n    node [shape=box, fontname=\"monospace\"];\n");
+        let mut next = 0;
+
+        self.dot_node(depth, visits, &mut next, &mut dot);
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// JSON of the same subtree as `dot`, in which each node holds its children
+    pub fn json(&self, depth: usize, visits: usize) -> String {
+        let mut json = String::new();
+
+        self.json_node(depth, visits, 0, &mut json);
+
+        json.push('\n');
+        json
+    }
+
+    /// Adds the node with the next free id and its subtree, returns the id
+    fn dot_node(&self, depth: usize, visits: usize, next: &mut usize, dot: &mut String) -> usize {
+        let id = *next;
+        *next += 1;
+
+        let fields = self
+            .fields()
+            .iter()
+            .map(|(name, value)| match (*name, value.as_str()) {
+                ("move", "null") => "root".to_string(),
+                _ => format!("{name} {}", value.trim_matches('"')),
+            })
+            .collect::<Vec<_>>()
+            .join("\\n");
+        let style = if self.game_state() == Default::default() {
+            ""
+        } else {
+            ", style=filled, fillcolor=\"#d5c4a1\""
+        };
+
+        dot.push_str(&format!("    n{id} [label=\"{fields}\"{style}];\n"));
+
+        for child in self.exported(depth, visits) {
+            let child_id = child.dot_node(depth - 1, visits, next, dot);
+            dot.push_str(&format!("    n{id} -> n{child_id};\n"));
+        }
+
+        id
+    }
+
+    fn json_node(&self, depth: usize, visits: usize, indent: usize, json: &mut String) {
+        let pad = " ".repeat(indent + 2);
+
+        json.push_str("{\n");
+        for (name, value) in self.fields() {
+            json.push_str(&format!("{pad}\"{name}\": {value},\n"));
+        }
+
+        let children = self.exported(depth, visits);
+        if children.is_empty() {
+            json.push_str(&format!("{pad}\"children\": []\n"));
+        } else {
+            json.push_str(&format!("{pad}\"children\": [\n"));
+
+            for (i, child) in children.iter().enumerate() {
+                json.push_str(&format!("{pad}  "));
+                child.json_node(depth - 1, visits, indent + 4, json);
+
+                if i + 1 < children.len() {
+                    json.push(',');
+                }
+                json.push('\n');
+            }
+
+            json.push_str(&format!("{pad}]\n"));
+        }
+
+        json.push_str(&format!("{}}}", " ".repeat(indent)));
+    }
+
+    /// Children, which are exported below the node, with the most visited first
+    fn exported(&self, depth: usize, visits: usize) -> Vec<Arc<Node>> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut children: Vec<_> = self
+            .children()
+            .filter(|c| c.stats().visits >= visits)
+            .collect();
+        children.sort_by_key(|c| std::cmp::Reverse(c.stats().visits));
+
+        children
+    }
+
+    /// Statistics of the node as JSON values, the root has no move
+    fn fields(&self) -> [(&'static str, String); 7] {
+        let stats = self.stats();
+        let reward = if stats.visits == 0 {
+            0.0
+        } else {
+            stats.reward / stats.visits as f32
+        };
+
+        [
+            (
+                "move",
+                self.mov()
+                    .map_or("null".to_string(), |mov| format!("\"{mov}\"")),
+            ),
+            ("visits", stats.visits.to_string()),
+            ("reward", format!("{reward:.4}")),
+            ("availability", stats.availability.to_string()),
+            ("policy", format!("{:.4}", *self.policy())),
+            ("value", format!("{:.4}", stats.value)),
+            ("state", format!("\"{:?}\"", self.game_state())),
+        ]
+    }
+}
@@ -1,10 +1,10 @@
-use super::{iteration, node::Node, Search};
+use super::{iteration, node::Node, Limits, Parallelism, Ponder, Search, Stop, TimeManager};
 use crate::{
     deployment::Deployment,
     information::Information,
     policy::Policy,
-    select::Select,
-    stratego::{Move, StrategoState},
+    select::{FinalSelect, Select},
+    stratego::{Army, Move, Observation, StrategoState},
     value::Value,
 };
 use ordered_float::OrderedFloat;
@@ -12,12 +12,20 @@ use rand::distr::weighted::WeightedIndex;
 use std::sync::Arc;
 
 pub struct ISMCTS<const MULTIPLE: bool> {
-    iterations: usize,
+    root: Arc<Node>,
+    pos: Option<StrategoState>,
+    limits: Limits,
+    time: TimeManager,
+    stop: Stop,
     value: Value,
     policy: Policy,
     select: Select,
+    final_select: FinalSelect,
     deployment: Deployment,
     information: Information,
+    parallelism: Parallelism,
+    reuse: bool,
+    pondered: bool,
 }
 
 impl<const MULTIPLE: bool> Search for ISMCTS<MULTIPLE> {
@@ -46,8 +54,8 @@ impl<const MULTIPLE: bool> Search for ISMCTS<MULTIPLE> {
         self.policy.get(pos, moves)
     }
 
-    fn deployment(&self) -> String {
-        self.deployment.get()
+    fn deployment(&self, army: &Army) -> String {
+        self.deployment.get(army)
     }
 
     fn information(&self, pos: &StrategoState) -> StrategoState {
@@ -65,24 +73,89 @@ impl<const MULTIPLE: bool> ISMCTS<MULTIPLE> {
         information: Information,
     ) -> Self {
         Self {
-            iterations,
+            root: Node::new(),
+            pos: None,
+            limits: Limits::iterations(iterations),
+            time: TimeManager::default(),
+            stop: Stop::default(),
             value,
             policy,
             select,
+            final_select: FinalSelect::default(),
             deployment,
             information,
+            parallelism: Parallelism::default(),
+            reuse: false,
+            pondered: false,
         }
     }
 
-    pub fn go(&mut self, pos: &StrategoState) -> Move {
-        let root = Node::new();
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
 
-        for _ in 0..self.iterations {
-            let mut det = self.information.get(&pos);
-            let node = Arc::clone(&root);
+    /// Choice of the played move among the children of the root
+    pub fn set_final_select(&mut self, final_select: FinalSelect) {
+        self.final_select = final_select;
+    }
 
-            iteration::execute_one::<ISMCTS<MULTIPLE>, MULTIPLE>(&mut det, node, self);
-        }
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Limits of all following searches, which replace the iterations given on creation
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn set_time_manager(&mut self, time: TimeManager) {
+        self.time = time;
+    }
+
+    /// Handle, which stops the running search from another thread
+    pub fn stop_handle(&self) -> Stop {
+        self.stop.clone()
+    }
+
+    /// Whether following searches continue on the subtree of the last search, which the
+    /// observed moves since lead to, instead of a new tree
+    pub fn set_reuse(&mut self, reuse: bool) {
+        self.reuse = reuse;
+    }
+
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    pub fn go(&mut self, pos: &Observation) -> Move {
+        self.stop.reset();
+        let budget = self.limits.start(&self.time, pos.state().ply(), &self.stop);
+
+        let root = match self.parallelism {
+            Parallelism::Tree(threads) => {
+                let root = if self.reuse || self.pondered {
+                    self.descend(pos.state())
+                } else {
+                    None
+                }
+                .unwrap_or_default();
+
+                iteration::execute::<Self, MULTIPLE>(
+                    &root,
+                    pos.state(),
+                    &budget,
+                    threads,
+                    self,
+                    true,
+                );
+
+                root
+            }
+            // Merged root has no subtrees, which could be reused
+            Parallelism::Root(threads) => {
+                iteration::execute_root::<Self, MULTIPLE>(pos.state(), &budget, threads, self, true)
+            }
+        };
 
         #[cfg(feature = "info")]
         {
@@ -103,6 +176,79 @@ impl<const MULTIPLE: bool> ISMCTS<MULTIPLE> {
             }
         }
 
-        root.max_visits().unwrap().mov().unwrap()
+        self.root = Arc::clone(&root);
+        self.pos = Some(pos.state().clone());
+        self.pondered = false;
+
+        self.final_select.get(&root.children_stats()).unwrap()
+    }
+
+    /// Subtree of the last search, which corresponds to `pos`
+    fn descend(&self, pos: &StrategoState) -> Option<Arc<Node>> {
+        let previous = self.pos.as_ref()?;
+        iteration::descend(&self.root, previous, pos)
+    }
+}
+
+impl<const MULTIPLE: bool> Ponder for ISMCTS<MULTIPLE> {
+    fn stop_handle(&self) -> Stop {
+        ISMCTS::stop_handle(self)
+    }
+
+    /// Searches the observation after our own move until stopped, at most for the iterations
+    /// and nodes of a search, see `Limits::pondering`
+    ///
+    /// The next `go` continues on the subtree of the opponent's reply, even without reuse.
+    fn ponder(&mut self, pos: &Observation) {
+        let limits = self.limits.pondering();
+        let budget = limits.start(&self.time, pos.state().ply(), &self.stop);
+
+        let threads = match self.parallelism {
+            Parallelism::Tree(threads) | Parallelism::Root(threads) => threads,
+        };
+
+        let root = self.descend(pos.state()).unwrap_or_default();
+        iteration::execute::<Self, MULTIPLE>(&root, pos.state(), &budget, threads, self, true);
+
+        self.root = root;
+        self.pos = Some(pos.state().clone());
+        self.pondered = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stratego::StrategoState;
+    use std::{sync::mpsc, thread, time::Duration};
+
+    #[test]
+    fn ponder_stops_on_its_own() {
+        let mut search = ISMCTS::<false>::new(
+            200,
+            Value::Heuristic(750.0),
+            Policy::Uniform,
+            Select::ISUCT(1.41),
+            Deployment::Random,
+            Information::Random,
+        );
+
+        let mut pos = StrategoState::from("d2f4/bbg4c/1m3dsc/8/8/BD3M1G/F5SD/1BCC4 r");
+        pos.make(pos.gen()[0]);
+        let observation = pos.observe(0);
+
+        // Nobody stops the search, so it has to return after its iterations
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            search.ponder(&observation);
+            sender.send(search.root().stats().visits).unwrap();
+        });
+
+        let visits = receiver.recv_timeout(Duration::from_secs(60)).unwrap();
+        assert!(visits <= 200);
+
+        let limits = Limits::movetime(Duration::from_secs(1)).pondering();
+        assert_eq!(limits.nodes, Some(Limits::PONDER_NODES));
+        assert_eq!(limits.movetime, None);
     }
 }
@@ -1,17 +1,167 @@
-use super::{node::Node, Search};
+use super::{
+    limits::Budget,
+    node::{Node, NodeStats},
+    Search,
+};
 use crate::{
     policy::{ordered, policy, DEFAULT_WEIGHTS},
-    stratego::{GameState, StrategoState},
+    stratego::{GameState, Move, StrategoState},
     value::{self},
 };
 use rand::distr::Distribution;
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+/// Runs iterations from `pos` on the tree of `root`, which `threads` share, until the
+/// budget is exhausted
+///
+/// Each iteration starts from a new determinization of `pos` if `determinize` is set and
+/// from `pos` itself otherwise.
+pub fn execute<S: Search + Sync, const MULTIPLE: bool>(
+    root: &Arc<Node>,
+    pos: &StrategoState,
+    budget: &Budget,
+    threads: usize,
+    search: &S,
+    determinize: bool,
+) {
+    let virtual_loss = threads > 1;
+    let next = AtomicUsize::new(0);
+    let nodes = AtomicUsize::new(0);
+
+    let run = || {
+        let mut current = pos.clone();
+        let ply = current.ply();
+
+        while !budget.exhausted(
+            next.fetch_add(1, Ordering::Relaxed),
+            nodes.load(Ordering::Relaxed),
+        ) {
+            let node = Arc::clone(root);
+
+            let added = if determinize {
+                let mut det = search.information(pos);
+                execute_one::<S, MULTIPLE>(&mut det, node, search, virtual_loss)
+            } else {
+                let added = execute_one::<S, MULTIPLE>(&mut current, node, search, virtual_loss);
+                current.rewind(ply);
+
+                added
+            };
+
+            if added {
+                nodes.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    };
+
+    if threads <= 1 {
+        run();
+    } else {
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                scope.spawn(run);
+            }
+        });
+    }
+}
+
+/// Runs iterations from `pos` on a separate tree for each of the `threads`, which share the
+/// budget, and returns a root, whose children hold the merged statistics of all trees
+pub fn execute_root<S: Search + Sync, const MULTIPLE: bool>(
+    pos: &StrategoState,
+    budget: &Budget,
+    threads: usize,
+    search: &S,
+    determinize: bool,
+) -> Arc<Node> {
+    let threads = threads.max(1);
+
+    let roots: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let budget = budget.split(threads, i);
+
+                scope.spawn(move || {
+                    let root = Node::new();
+                    execute::<S, MULTIPLE>(&root, pos, &budget, 1, search, determinize);
+
+                    root
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect()
+    });
+
+    let mut stats = NodeStats::new(0, 0.0);
+    let mut children = HashMap::new();
+    for root in &roots {
+        stats.merge(&root.stats());
+        merge(&mut children, root);
+    }
+
+    Node::from_stats(stats, children)
+}
+
+/// Node of the tree of `root`, which was searched from `previous`, that corresponds to `pos`
+/// later in the same game
+///
+/// The node is detached from its parent and becomes the new root, its statistics are kept.
+/// Returns `None` if `pos` is from another game or the tree doesn't reach it.
+pub fn descend(
+    root: &Arc<Node>,
+    previous: &StrategoState,
+    pos: &StrategoState,
+) -> Option<Arc<Node>> {
+    let ply = previous.ply();
+    let history = pos.history();
+
+    if history.len() < ply || history[..ply] != previous.history() {
+        return None;
+    }
+
+    let mut start = pos.clone();
+    start.rewind(ply);
+    if start.board() != previous.board() {
+        return None;
+    }
+
+    let mut node = Arc::clone(root);
+    for mov in &history[ply..] {
+        node = node.child(mov)?;
+    }
+
+    node.detach();
+    Some(node)
+}
 
+/// Adds the statistics of the children of `node` to the ones of the same moves
+pub fn merge(children: &mut HashMap<Move, NodeStats>, node: &Node) {
+    node.children().for_each(|c| {
+        children
+            .entry(c.mov().unwrap())
+            .or_insert(NodeStats::new(0, 0.0))
+            .merge(&c.stats());
+    });
+}
+
+/// Runs one iteration and returns whether it added a node to the tree
 pub fn execute_one<S: Search, const MULTIPLE: bool>(
     pos: &mut StrategoState,
     mut node: Arc<Node>,
     search: &S,
-) {
+    virtual_loss: bool,
+) -> bool {
     let mut rng = rand::rng();
 
     let mut moves: Vec<_>;
@@ -39,29 +189,42 @@ pub fn execute_one<S: Search, const MULTIPLE: bool>(
 
         node = search.select(&node, &moves).unwrap();
         pos.make(node.mov().unwrap());
+
+        if virtual_loss {
+            node.add_virtual_loss();
+        }
     }
 
-    if untried.len() != 0 {
+    let added = untried.len() != 0;
+    if added {
         let i = search.policy(&pos, &untried).sample(&mut rng);
         pos.make(untried[i]);
 
-        node = node.add(untried[i], pos.game_state(), heuristic(pos));
+        node = node.add(untried[i], pos.game_state(), heuristic(pos), virtual_loss);
     }
 
     let mut reward = -utility(pos, search);
 
     let mut previous = node;
     loop {
+        let parent = previous.parent();
+
+        // Root is never selected, so it has no virtual loss
+        if virtual_loss && parent.is_some() {
+            previous.remove_virtual_loss();
+        }
+
         previous.update(reward);
         reward = -reward;
 
-        let parent = previous.parent();
         if let Some(node) = parent {
             previous = node;
         } else {
             break;
         }
     }
+
+    added
 }
 
 fn utility<S: Search>(pos: &mut StrategoState, search: &S) -> f32 {
@@ -81,3 +244,34 @@ fn heuristic(pos: &mut StrategoState) -> f32 {
         GameState::Loss => -1.0,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stratego::Flag;
+
+    #[test]
+    fn descend_after_attack_on_hidden_piece() {
+        let mut game = StrategoState::from("d2f4/bbg4c/1m3dsc/1M6/8/BD5G/F5SD/1BCC4 r");
+        let previous = game.observe(0);
+
+        // Red marshal attacks the hidden blue piece in front of it
+        let attack = game
+            .gen()
+            .iter()
+            .find(|mov| (mov.flag & Flag::CAPTURE) != 0 && mov.to == mov.from + 10)
+            .unwrap();
+        game.make(attack);
+        game.make(game.gen()[0]);
+
+        let pos = game.observe(0);
+        let root = Node::new();
+        let mut node = Arc::clone(&root);
+        for mov in pos.state().history() {
+            node = node.add(mov, GameState::Ongoing, 0.0, false);
+        }
+
+        let reused = descend(&root, previous.state(), pos.state()).unwrap();
+        assert!(Arc::ptr_eq(&reused, &node));
+    }
+}
@@ -0,0 +1,207 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Remaining time of one side and the time added after each of its moves
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Clock {
+    pub remaining: Duration,
+    pub increment: Duration,
+}
+
+impl Clock {
+    pub fn new(remaining: Duration, increment: Duration) -> Self {
+        Self {
+            remaining,
+            increment,
+        }
+    }
+
+    /// Charges a move, which took `elapsed`, or returns `None` if the time ran out
+    pub fn charge(&self, elapsed: Duration) -> Option<Self> {
+        let remaining = self.remaining.checked_sub(elapsed)?;
+
+        Some(Self {
+            remaining: remaining + self.increment,
+            ..*self
+        })
+    }
+}
+
+/// Handle, which stops the running search of its owner from another thread
+///
+/// Each search clears it when it starts, so a stop only ends the search running at that
+/// time. The search still returns its best move so far.
+#[derive(Clone, Default, Debug)]
+pub struct Stop(Arc<AtomicBool>);
+
+impl Stop {
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn stopped(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn reset(&self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Budget of a search for one move, every limit, which is set, stops it
+///
+/// A search without any limit doesn't stop, so at least one of them should be set.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Limits {
+    pub iterations: Option<usize>,
+    /// Wall-clock time per move
+    pub movetime: Option<Duration>,
+    /// Game clock of the side to move, of which the time manager allots a share to the move
+    pub clock: Option<Clock>,
+    /// Nodes added to the tree
+    pub nodes: Option<usize>,
+}
+
+impl Limits {
+    /// Nodes, which pondering adds at most, if neither the iterations nor the nodes are limited
+    pub const PONDER_NODES: usize = 1_000_000;
+
+    pub fn iterations(iterations: usize) -> Self {
+        Self {
+            iterations: Some(iterations),
+            ..Self::default()
+        }
+    }
+
+    pub fn movetime(movetime: Duration) -> Self {
+        Self {
+            movetime: Some(movetime),
+            ..Self::default()
+        }
+    }
+
+    /// Limits of pondering, which runs until it is stopped instead of using the time
+    ///
+    /// Only the iterations and nodes of a search apply, without them the tree is bounded by
+    /// `PONDER_NODES`, so that it doesn't grow until the memory runs out.
+    pub fn pondering(&self) -> Self {
+        let nodes = match (self.iterations, self.nodes) {
+            (None, None) => Some(Limits::PONDER_NODES),
+            (_, nodes) => nodes,
+        };
+
+        Self {
+            iterations: self.iterations,
+            nodes,
+            ..Self::default()
+        }
+    }
+
+    /// Budget of a search, which starts now at `ply` and ends early on `stop`
+    pub(crate) fn start(&self, manager: &TimeManager, ply: usize, stop: &Stop) -> Budget {
+        let time = [
+            self.movetime,
+            self.clock.map(|clock| manager.allot(&clock, ply)),
+        ];
+
+        Budget {
+            iterations: self.iterations.unwrap_or(usize::MAX),
+            nodes: self.nodes.unwrap_or(usize::MAX),
+            deadline: time
+                .into_iter()
+                .flatten()
+                .min()
+                .map(|time| Instant::now() + time),
+            stop: stop.clone(),
+        }
+    }
+}
+
+/// Splits the remaining time on the clock across the moves expected until the end of the game
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeManager {
+    /// Expected number of moves of each side in a game
+    pub moves: usize,
+    /// Number of moves, which the remaining time is at least split over
+    pub minimum: usize,
+    /// Time kept back for the overhead of each move, e.g. of creating the observation
+    pub overhead: Duration,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        Self {
+            moves: 150,
+            minimum: 20,
+            overhead: Duration::from_millis(10),
+        }
+    }
+}
+
+impl TimeManager {
+    /// Time of the move at `ply` of the game
+    pub fn allot(&self, clock: &Clock, ply: usize) -> Duration {
+        let left = self.moves.saturating_sub(ply / 2).max(self.minimum).max(1);
+        let available = clock.remaining.saturating_sub(self.overhead);
+
+        (clock.remaining / left as u32 + clock.increment).min(available)
+    }
+}
+
+/// Limits of a running search
+#[derive(Clone, Debug)]
+pub(crate) struct Budget {
+    iterations: usize,
+    nodes: usize,
+    deadline: Option<Instant>,
+    stop: Stop,
+}
+
+impl Budget {
+    /// Whether the search has to stop after `iterations` iterations, which added `nodes` nodes
+    ///
+    /// The time only runs out and a stop only applies after the first iteration, so that the
+    /// root has a child.
+    pub fn exhausted(&self, iterations: usize, nodes: usize) -> bool {
+        iterations >= self.iterations
+            || nodes >= self.nodes
+            || (iterations > 0
+                && (self.stop.stopped()
+                    || self
+                        .deadline
+                        .is_some_and(|deadline| Instant::now() >= deadline)))
+    }
+
+    /// Share of the `i`-th of `parts` searches, which run at the same time
+    pub fn split(&self, parts: usize, i: usize) -> Self {
+        let share = |total: usize| match total {
+            usize::MAX => usize::MAX,
+            total => total / parts + usize::from(i < total % parts),
+        };
+
+        Self {
+            iterations: share(self.iterations),
+            nodes: share(self.nodes),
+            ..self.clone()
+        }
+    }
+
+    /// Budget of the next of `parts` searches, which run one after another, it gets an equal
+    /// share of the remaining time
+    pub fn next(&self, parts: usize) -> Self {
+        let deadline = self.deadline.map(|deadline| {
+            let now = Instant::now();
+            now + deadline.saturating_duration_since(now) / parts.max(1) as u32
+        });
+
+        Self {
+            deadline,
+            ..self.clone()
+        }
+    }
+}
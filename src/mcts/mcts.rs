@@ -1,23 +1,32 @@
-use super::{iteration, node::Node, Search};
+use super::{
+    iteration,
+    node::{Node, NodeStats},
+    Limits, Parallelism, Search, Stop, TimeManager,
+};
 use crate::{
     deployment::Deployment,
     policy::Policy,
-    select::Select,
-    stratego::{Move, StrategoState},
+    select::{FinalSelect, Select},
+    stratego::{Army, Move, StrategoState},
     value::Value,
 };
 use ordered_float::OrderedFloat;
 use rand::distr::weighted::WeightedIndex;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 pub struct MCTS {
     root: Arc<Node>,
     pos: Option<StrategoState>,
-    iterations: usize,
+    limits: Limits,
+    time: TimeManager,
+    stop: Stop,
     value: Value,
     policy: Policy,
     select: Select,
+    final_select: FinalSelect,
     deployment: Deployment,
+    parallelism: Parallelism,
+    reuse: bool,
 }
 
 impl Search for MCTS {
@@ -44,8 +53,8 @@ impl Search for MCTS {
         self.policy.get(pos, moves)
     }
 
-    fn deployment(&self) -> String {
-        self.deployment.get()
+    fn deployment(&self, army: &Army) -> String {
+        self.deployment.get(army)
     }
 
     fn information(&self, pos: &StrategoState) -> StrategoState {
@@ -64,16 +73,65 @@ impl MCTS {
         Self {
             root: Node::new(),
             pos: None,
-            iterations,
+            limits: Limits::iterations(iterations),
+            time: TimeManager::default(),
+            stop: Stop::default(),
             value,
             policy,
             select,
+            final_select: FinalSelect::default(),
             deployment,
+            parallelism: Parallelism::default(),
+            reuse: false,
         }
     }
 
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
+
+    /// Choice of the played move among the children of the root
+    pub fn set_final_select(&mut self, final_select: FinalSelect) {
+        self.final_select = final_select;
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Limits of all following searches, which replace the iterations given on creation
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    pub fn set_time_manager(&mut self, time: TimeManager) {
+        self.time = time;
+    }
+
+    /// Handle, which stops the running search from another thread
+    pub fn stop_handle(&self) -> Stop {
+        self.stop.clone()
+    }
+
+    /// Whether following searches continue on the subtree of the last search, which
+    /// corresponds to the new position, instead of a new tree
+    pub fn set_reuse(&mut self, reuse: bool) {
+        self.reuse = reuse;
+    }
+
     pub fn go(&mut self, pos: &StrategoState) -> Move {
-        self.set_root(pos);
+        let reused = match &self.pos {
+            Some(previous) if self.reuse => iteration::descend(&self.root, previous, pos),
+            _ => None,
+        };
+
+        match reused {
+            Some(root) => {
+                self.root = root;
+                self.pos = Some(pos.clone());
+            }
+            None => self.set_root(pos),
+        }
         self.run(pos);
 
         #[cfg(feature = "info")]
@@ -93,15 +151,35 @@ impl MCTS {
             }
         }
 
-        self.root.max_visits().unwrap().mov().unwrap()
+        self.final_select.get(&self.root.children_stats()).unwrap()
     }
 
+    /// Searches `pos` from the current root, a root-parallel search replaces the tree by its
+    /// root with the merged statistics
     pub fn run(&mut self, pos: &StrategoState) {
-        for _ in 0..self.iterations {
-            let mut pos = pos.clone();
-            let node = Arc::clone(&self.root);
+        self.stop.reset();
+        let budget = self.limits.start(&self.time, pos.ply(), &self.stop);
 
-            iteration::execute_one::<MCTS, false>(&mut pos, node, self);
+        match self.parallelism {
+            Parallelism::Tree(threads) => {
+                let root = Arc::clone(&self.root);
+                iteration::execute::<MCTS, false>(&root, pos, &budget, threads, self, false);
+            }
+            Parallelism::Root(threads) => {
+                let merged =
+                    iteration::execute_root::<MCTS, false>(pos, &budget, threads, self, false);
+
+                // Keep the statistics of previous runs
+                let mut stats = NodeStats::new(0, 0.0);
+                stats.merge(&self.root.stats());
+                stats.merge(&merged.stats());
+
+                let mut children = HashMap::new();
+                iteration::merge(&mut children, &self.root);
+                iteration::merge(&mut children, &merged);
+
+                self.root = Node::from_stats(stats, children);
+            }
         }
     }
 
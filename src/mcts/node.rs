@@ -1,11 +1,20 @@
 use crate::stratego::{GameState, Move};
-use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak};
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard, Weak},
+};
 
+#[derive(Clone, Copy)]
 pub struct NodeStats {
     pub visits: usize,
     pub availability: usize,
     pub reward: f32,
     pub value: f32,
+    /// Iterations of other threads, which have selected the node without updating it yet
+    ///
+    /// Each of them is counted as visit with a loss, so that the threads of a tree-parallel
+    /// search spread over the tree.
+    pub virtual_loss: usize,
 }
 
 impl NodeStats {
@@ -15,45 +24,100 @@ impl NodeStats {
             availability,
             reward: 0.0,
             value,
+            virtual_loss: 0,
         }
     }
+
+    /// Adds the statistics of the same node of another tree
+    pub fn merge(&mut self, other: &NodeStats) {
+        self.visits += other.visits;
+        self.availability += other.availability;
+        self.reward += other.reward;
+    }
 }
 
 pub struct Node {
     mov: Option<Move>,
-    parent: Option<Weak<Node>>,
+    parent: RwLock<Option<Weak<Node>>>,
     state: RwLock<GameState>,
     policy: RwLock<f32>,
     children: RwLock<Vec<Arc<Node>>>,
     stats: RwLock<NodeStats>,
 }
 
-impl Node {
-    pub fn new() -> Arc<Self> {
-        Arc::new(Self {
+impl Default for Node {
+    fn default() -> Self {
+        Self {
             mov: None,
-            parent: None,
+            parent: RwLock::new(None),
             state: RwLock::new(GameState::default()),
             policy: RwLock::new(0.0),
             children: Default::default(),
             stats: RwLock::new(NodeStats::new(0, 0.0)),
-        })
+        }
     }
+}
 
-    pub fn add(self: Arc<Self>, mov: Move, state: GameState, value: f32) -> Arc<Node> {
-        let mut children = self.children.write().unwrap();
-        let parent = Arc::downgrade(&self);
+impl Node {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
 
-        let child = Arc::new(Node {
-            mov: Some(mov),
-            parent: Some(parent),
-            state: RwLock::new(state),
-            policy: RwLock::new(0.0),
-            children: Default::default(),
-            stats: RwLock::new(NodeStats::new(1, value)),
+    /// Root, whose children have the given statistics, e.g. merged from several trees
+    pub fn from_stats(stats: NodeStats, children: HashMap<Move, NodeStats>) -> Arc<Self> {
+        let root = Arc::new(Self {
+            stats: RwLock::new(stats),
+            ..Self::default()
         });
 
-        children.push(Arc::clone(&child));
+        let parent = Arc::downgrade(&root);
+        *root.children.write().unwrap() = children
+            .into_iter()
+            .map(|(mov, stats)| {
+                Arc::new(Node {
+                    mov: Some(mov),
+                    parent: RwLock::new(Some(parent.clone())),
+                    stats: RwLock::new(stats),
+                    ..Self::default()
+                })
+            })
+            .collect();
+
+        root
+    }
+
+    /// Adds the child of `mov`, unless another thread has added it already
+    ///
+    /// The virtual loss is added to the child before any other thread can select it.
+    pub fn add(
+        self: Arc<Self>,
+        mov: Move,
+        state: GameState,
+        value: f32,
+        virtual_loss: bool,
+    ) -> Arc<Node> {
+        let mut children = self.children.write().unwrap();
+
+        let child = match children.iter().find(|c| c.mov == Some(mov)) {
+            Some(child) => Arc::clone(child),
+            None => {
+                let child = Arc::new(Node {
+                    mov: Some(mov),
+                    parent: RwLock::new(Some(Arc::downgrade(&self))),
+                    state: RwLock::new(state),
+                    stats: RwLock::new(NodeStats::new(1, value)),
+                    ..Self::default()
+                });
+
+                children.push(Arc::clone(&child));
+                child
+            }
+        };
+
+        if virtual_loss {
+            child.add_virtual_loss();
+        }
+
         child
     }
 
@@ -72,12 +136,88 @@ impl Node {
         stats.reward += reward;
     }
 
+    pub fn add_virtual_loss(&self) {
+        let mut stats = self.stats.write().unwrap();
+
+        stats.visits += 1;
+        stats.reward -= 1.0;
+        stats.virtual_loss += 1;
+    }
+
+    pub fn remove_virtual_loss(&self) {
+        let mut stats = self.stats.write().unwrap();
+
+        stats.visits -= 1;
+        stats.reward += 1.0;
+        stats.virtual_loss -= 1;
+    }
+
     pub fn mov(&self) -> Option<Move> {
         self.mov
     }
 
     pub fn parent(&self) -> Option<Arc<Node>> {
-        self.parent.as_ref().and_then(Weak::upgrade)
+        self.parent.read().unwrap().as_ref().and_then(Weak::upgrade)
+    }
+
+    /// Makes the node the root of its subtree, so that updates stop at it
+    pub fn detach(&self) {
+        *self.parent.write().unwrap() = None;
+    }
+
+    /// Child, whose move is consistent with the observed `mov`
+    ///
+    /// Opponent moves of different determinizations only differ in their pieces, so several
+    /// children can match a move with an unknown piece. These are merged with their subtrees
+    /// into a new node, whose statistics add up theirs.
+    pub fn child(&self, mov: &Move) -> Option<Arc<Node>> {
+        let consistent: Vec<_> = self
+            .children()
+            .filter(|c| c.mov.is_some_and(|m| m.consistent(mov)))
+            .collect();
+
+        (!consistent.is_empty()).then(|| Node::merged(consistent))
+    }
+
+    /// Node, which merges `nodes` and the children of the same moves below them recursively
+    ///
+    /// The move, value, policy and game state are the ones of the most visited node. A single
+    /// node is kept as it is, the children of a merged node are moved to it.
+    fn merged(nodes: Vec<Arc<Node>>) -> Arc<Node> {
+        let most = nodes
+            .iter()
+            .max_by_key(|node| node.stats().visits)
+            .cloned()
+            .unwrap();
+
+        if nodes.len() == 1 {
+            return most;
+        }
+
+        let mut stats = NodeStats::new(0, most.stats().value);
+        nodes.iter().for_each(|node| stats.merge(&node.stats()));
+
+        let mut moves: HashMap<Move, Vec<Arc<Node>>> = HashMap::new();
+        for child in nodes.iter().flat_map(|node| node.children()) {
+            moves.entry(child.mov.unwrap()).or_default().push(child);
+        }
+
+        let node = Arc::new(Node {
+            mov: most.mov,
+            parent: RwLock::new(most.parent.read().unwrap().clone()),
+            state: RwLock::new(most.game_state()),
+            policy: RwLock::new(*most.policy()),
+            children: Default::default(),
+            stats: RwLock::new(stats),
+        });
+
+        let children: Vec<_> = moves.into_values().map(Node::merged).collect();
+        for child in &children {
+            *child.parent.write().unwrap() = Some(Arc::downgrade(&node));
+        }
+        *node.children.write().unwrap() = children;
+
+        node
     }
 
     pub fn children(&self) -> impl Iterator<Item = Arc<Node>> {
@@ -112,7 +252,71 @@ impl Node {
         self.parent().unwrap().stats.read().unwrap().visits
     }
 
+    /// Moves of the children with a copy of their statistics
+    pub fn children_stats(&self) -> Vec<(Move, NodeStats)> {
+        self.children()
+            .map(|c| (c.mov().unwrap(), *c.stats()))
+            .collect()
+    }
+
     pub fn max_visits(&self) -> Option<Arc<Node>> {
         self.children().max_by_key(|c| c.stats().visits)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stratego::{Flag, Piece};
+
+    fn mov(from: u8, to: u8, piece: usize) -> Move {
+        Move {
+            from,
+            to,
+            flag: Flag::QUIET,
+            piece: piece as u8,
+        }
+    }
+
+    /// Adds the child of `mov` to `node` and visits it `visits` times
+    fn visit(node: &Arc<Node>, mov: Move, visits: usize) -> Arc<Node> {
+        let child = Arc::clone(node).add(mov, GameState::Ongoing, 0.0, false);
+        (0..visits).for_each(|_| child.update(1.0));
+
+        child
+    }
+
+    #[test]
+    fn child_merges_consistent_children() {
+        let root = Node::new();
+        let marshal = visit(&root, mov(11, 21, Piece::MARSHAL), 3);
+        let general = visit(&root, mov(11, 21, Piece::GENERAL), 2);
+        let other = visit(&root, mov(12, 22, Piece::MARSHAL), 7);
+
+        let reply = mov(61, 51, Piece::SCOUT);
+        let below = visit(&visit(&marshal, reply, 2), mov(21, 31, Piece::MARSHAL), 1);
+        visit(&general, reply, 1);
+        visit(&general, mov(62, 52, Piece::MINER), 1);
+
+        let child = root.child(&mov(11, 21, Piece::UNKNOWN)).unwrap();
+        assert_eq!(child.stats().visits, 5);
+        assert_eq!(child.stats().availability, 2);
+        assert_eq!(child.stats().reward, 5.0);
+        assert_eq!(child.mov(), Some(mov(11, 21, Piece::MARSHAL)));
+
+        let visits: usize = child.children().map(|c| c.stats().visits).sum();
+        assert_eq!(visits, 4);
+
+        let merged = child.children().find(|c| c.mov() == Some(reply)).unwrap();
+        assert_eq!(merged.stats().visits, 3);
+        assert!(Arc::ptr_eq(&merged.parent().unwrap(), &child));
+
+        // Subtrees below a merged node are kept and updates reach it
+        assert!(Arc::ptr_eq(&below.parent().unwrap(), &merged));
+
+        // Known pieces only match their own child, which is kept as it is
+        let child = root.child(&mov(12, 22, Piece::MARSHAL)).unwrap();
+        assert!(Arc::ptr_eq(&child, &other));
+        assert!(root.child(&mov(12, 22, Piece::GENERAL)).is_none());
+    }
+}
@@ -1,10 +1,10 @@
-use super::{iteration, node::NodeStats, Node, Search};
+use super::{iteration, node::NodeStats, Limits, Node, Parallelism, Search, Stop, TimeManager};
 use crate::{
     deployment::Deployment,
     information::Information,
     policy::Policy,
-    select::Select,
-    stratego::{Move, StrategoState},
+    select::{FinalSelect, Select},
+    stratego::{Army, Move, Observation, StrategoState},
     value::Value,
 };
 use ordered_float::OrderedFloat;
@@ -13,12 +13,17 @@ use std::{collections::HashMap, sync::Arc};
 
 pub struct PIMC {
     determinizations: usize,
-    iterations: usize,
+    limits: Limits,
+    time: TimeManager,
+    stop: Stop,
     value: Value,
     policy: Policy,
     select: Select,
+    final_select: FinalSelect,
     deployment: Deployment,
     information: Information,
+    parallelism: Parallelism,
+    enumerate: bool,
 }
 
 impl Search for PIMC {
@@ -45,8 +50,8 @@ impl Search for PIMC {
         self.policy.get(pos, moves)
     }
 
-    fn deployment(&self) -> String {
-        self.deployment.get()
+    fn deployment(&self, army: &Army) -> String {
+        self.deployment.get(army)
     }
 
     fn information(&self, pos: &StrategoState) -> StrategoState {
@@ -66,48 +71,99 @@ impl PIMC {
     ) -> Self {
         Self {
             determinizations,
-            iterations,
+            limits: Limits::iterations(iterations),
+            time: TimeManager::default(),
+            stop: Stop::default(),
             value,
             policy,
             select,
+            final_select: FinalSelect::default(),
             deployment,
             information,
+            parallelism: Parallelism::default(),
+            enumerate: false,
         }
     }
 
-    pub fn go(&mut self, pos: &StrategoState) -> Move {
-        let mut root: HashMap<Move, NodeStats> = HashMap::new();
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
 
-        for _ in 0..self.determinizations {
-            let node = Node::new();
-            let det = self.information.get(pos);
+    /// Choice of the played move among the children of the root
+    pub fn set_final_select(&mut self, final_select: FinalSelect) {
+        self.final_select = final_select;
+    }
 
-            for _ in 0..self.iterations {
-                let mut pos = det.clone();
-                let node = Arc::clone(&node);
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
 
-                iteration::execute_one::<PIMC, false>(&mut pos, node, self);
-            }
+    /// Limits of all following searches, which replace the iterations given on creation
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
 
-            node.children().for_each(|c| {
-                let stats = c.stats();
-                let entry = root
-                    .entry(c.mov().unwrap())
-                    .or_insert(NodeStats::new(0, 0.0));
+    pub fn set_time_manager(&mut self, time: TimeManager) {
+        self.time = time;
+    }
+
+    /// Whether small information sets are searched exactly once per consistent world instead
+    /// of sampled, as soon as there are no more worlds than determinizations
+    ///
+    /// Every world counts the same, so it is the exact counterpart of `Information::Random`.
+    /// The likelihoods of `Information::Weighted` and the choice of `Information::Heuristic`
+    /// don't apply to the enumerated worlds.
+    pub fn set_enumerate(&mut self, enumerate: bool) {
+        self.enumerate = enumerate;
+    }
+
+    /// Handle, which stops the running search from another thread
+    pub fn stop_handle(&self) -> Stop {
+        self.stop.clone()
+    }
+
+    /// Searches each world within the limits, only the time is shared by all worlds
+    pub fn go(&mut self, pos: &Observation) -> Move {
+        self.stop.reset();
+        let budget = self.limits.start(&self.time, pos.state().ply(), &self.stop);
+        let mut root: HashMap<Move, NodeStats> = HashMap::new();
 
-                entry.visits += stats.visits;
-                entry.reward += stats.reward;
-            })
+        let worlds = self
+            .enumerate
+            .then(|| pos.state().determinizations(self.determinizations))
+            .flatten()
+            .unwrap_or_else(|| {
+                (0..self.determinizations)
+                    .map(|_| self.information.get(pos.state()))
+                    .collect()
+            });
+
+        let count = worlds.len();
+        for (i, det) in worlds.into_iter().enumerate() {
+            let budget = budget.next(count - i);
+            let node = match self.parallelism {
+                Parallelism::Tree(threads) => {
+                    let node = Node::new();
+                    iteration::execute::<PIMC, false>(&node, &det, &budget, threads, self, false);
+
+                    node
+                }
+                Parallelism::Root(threads) => {
+                    iteration::execute_root::<PIMC, false>(&det, &budget, threads, self, false)
+                }
+            };
+
+            iteration::merge(&mut root, &node);
         }
 
         #[allow(unused_mut)]
-        let mut children: Vec<_> = root.iter().collect();
+        let mut children: Vec<_> = root.into_iter().collect();
 
         #[cfg(feature = "info")]
         {
             children.sort_by_key(|c| c.1.visits);
             for c in &children {
-                let stats = c.1;
+                let stats = &c.1;
 
                 println!(
                     "info move {} visits {} reward {}",
@@ -116,10 +172,10 @@ impl PIMC {
             }
         }
 
-        *children.iter().max_by_key(|c| c.1.visits).unwrap().0
+        self.final_select.get(&children).unwrap()
     }
 
-    pub fn deployment(&self) -> String {
-        self.deployment.get()
+    pub fn deployment(&self, army: &Army) -> String {
+        self.deployment.get(army)
     }
 }
@@ -0,0 +1,70 @@
+use super::Stop;
+use crate::stratego::Observation;
+use std::thread::{self, JoinHandle};
+
+/// Search, which can continue on the opponent's time
+pub trait Ponder {
+    /// Handle, which stops the running search from another thread
+    fn stop_handle(&self) -> Stop;
+
+    /// Searches `pos`, in which the opponent is to move, until it is stopped or its limits
+    /// are used up
+    fn ponder(&mut self, pos: &Observation);
+}
+
+/// Owner of a search, which ponders in a background thread between its own moves
+pub struct Pondering<T: Ponder + Send + 'static> {
+    search: Option<T>,
+    background: Option<(Stop, JoinHandle<T>)>,
+}
+
+impl<T: Ponder + Send + 'static> Pondering<T> {
+    pub fn new(search: T) -> Self {
+        Self {
+            search: Some(search),
+            background: None,
+        }
+    }
+
+    /// Search, after pondering has been stopped
+    pub fn get(&mut self) -> &mut T {
+        self.stop();
+        self.search.as_mut().unwrap()
+    }
+
+    /// Ponders on `pos` in the background, until the search is needed again
+    pub fn start(&mut self, pos: &Observation) {
+        self.stop();
+
+        let mut search = self.search.take().unwrap();
+        let pos = pos.clone();
+
+        // Cleared before the thread starts, so that no stop can get lost
+        let stop = search.stop_handle();
+        stop.reset();
+
+        let handle = thread::spawn(move || {
+            search.ponder(&pos);
+            search
+        });
+        self.background = Some((stop, handle));
+    }
+
+    /// Stops pondering and waits for the search to return
+    pub fn stop(&mut self) {
+        if let Some((stop, handle)) = self.background.take() {
+            stop.stop();
+            self.search = Some(handle.join().unwrap());
+        }
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.background.is_some()
+    }
+}
+
+impl<T: Ponder + Send + 'static> Drop for Pondering<T> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -47,16 +47,8 @@ pub fn policy(pos: &StrategoState, mov: &Move, weights: &[f32; 5]) -> f32 {
     }
 
     let piece = mov.piece as usize;
-    let ordering = if (piece == Piece::SPY && other == Piece::MARSHAL)
-        || (piece == Piece::MINER && other == Piece::BOMB)
-    {
-        // Spy can capture general or miner can defuse bomb
-        Ordering::Greater
-    } else {
-        piece.cmp(&other)
-    };
 
-    return match ordering {
+    return match pos.rules().combat(piece, other) {
         Ordering::Less => weights[1],
         Ordering::Equal => weights[2],
         Ordering::Greater => weights[3],
@@ -1,6 +1,6 @@
 use crate::{
     deployment::Deployment,
-    stratego::{Move, StrategoState},
+    stratego::{Army, Move, Observation},
 };
 use rand::{rng, seq::IteratorRandom};
 
@@ -13,17 +13,13 @@ impl UniformRandom {
         Self { deployment }
     }
 
-    pub fn go(&mut self, pos: &StrategoState) -> Move {
+    pub fn go(&mut self, pos: &Observation) -> Move {
         let mut rng = rng();
 
-        pos.clone()
-            .gen()
-            .iter()
-            .choose(&mut rng)
-            .expect("valid move")
+        pos.gen().iter().choose(&mut rng).expect("valid move")
     }
 
-    pub fn deployment(&mut self) -> String {
-        self.deployment.get()
+    pub fn deployment(&mut self, army: &Army) -> String {
+        self.deployment.get(army)
     }
 }
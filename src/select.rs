@@ -1,4 +1,10 @@
-use crate::mcts::Node;
+use crate::{
+    mcts::{Node, NodeStats},
+    stratego::Move,
+};
+use ordered_float::OrderedFloat;
+use rand::distr::{weighted::WeightedIndex, Distribution};
+use std::cmp::Reverse;
 
 pub enum Select {
     UCT(f32),
@@ -26,6 +32,100 @@ impl Select {
     }
 }
 
+/// Choice of the move, which is played after the search, among the children of the root
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum FinalSelect {
+    #[default]
+    MaxVisits,
+    /// Highest mean reward
+    MaxReward,
+    /// Best by visits and mean reward together, i.e. with the lowest sum of both ranks
+    RobustMax,
+    /// Highest lower confidence bound of the mean reward with the given constant
+    LCB(f32),
+    /// Sampled proportional to visits^(1/T) with the temperature T, which is zero for the most
+    /// visited move
+    Temperature(f32),
+}
+
+impl FinalSelect {
+    /// Move of one of the `children` of the root with their statistics
+    pub fn get(&self, children: &[(Move, NodeStats)]) -> Option<Move> {
+        let best = |key: &dyn Fn(&NodeStats) -> f32| {
+            children
+                .iter()
+                .max_by_key(|(_, stats)| OrderedFloat(key(stats)))
+                .map(|(mov, _)| *mov)
+        };
+
+        match self {
+            FinalSelect::MaxVisits => best(&|stats| stats.visits as f32),
+            FinalSelect::MaxReward => best(&mean),
+            FinalSelect::RobustMax => robust_max(children),
+            FinalSelect::LCB(c) => {
+                let parent = children.iter().map(|(_, stats)| stats.visits).sum();
+                best(&|stats| lcb(stats, parent, *c))
+            }
+            FinalSelect::Temperature(t) if *t <= 0.0 => FinalSelect::MaxVisits.get(children),
+            FinalSelect::Temperature(t) => {
+                temperature(children, *t).or_else(|| FinalSelect::MaxVisits.get(children))
+            }
+        }
+    }
+}
+
+fn mean(stats: &NodeStats) -> f32 {
+    if stats.visits == 0 {
+        f32::NEG_INFINITY
+    } else {
+        stats.reward / stats.visits as f32
+    }
+}
+
+fn lcb(stats: &NodeStats, parent: usize, c: f32) -> f32 {
+    // Unvisited children have no bound, which would be 0/0 for a single parent visit
+    if stats.visits == 0 {
+        return f32::NEG_INFINITY;
+    }
+
+    let v = ((parent as f32).ln() / stats.visits as f32).sqrt();
+
+    mean(stats) - c * v
+}
+
+fn robust_max(children: &[(Move, NodeStats)]) -> Option<Move> {
+    let ranks = |key: fn(&NodeStats) -> f32| {
+        let mut order: Vec<_> = (0..children.len()).collect();
+        order.sort_by_key(|&i| Reverse(OrderedFloat(key(&children[i].1))));
+
+        let mut ranks = vec![0; children.len()];
+        order
+            .iter()
+            .enumerate()
+            .for_each(|(rank, &i)| ranks[i] = rank);
+        ranks
+    };
+
+    let visits = ranks(|stats| stats.visits as f32);
+    let reward = ranks(mean);
+
+    (0..children.len())
+        .min_by_key(|&i| (visits[i] + reward[i], visits[i]))
+        .map(|i| children[i].0)
+}
+
+fn temperature(children: &[(Move, NodeStats)], t: f32) -> Option<Move> {
+    let max = children.iter().map(|(_, stats)| stats.visits).max()?;
+
+    // Relative to the most visited move, so that low temperatures don't overflow
+    let weights = children
+        .iter()
+        .map(|(_, stats)| (stats.visits as f64 / max as f64).powf(1.0 / t as f64));
+    let distribution = WeightedIndex::new(weights).ok()?;
+
+    Some(children[distribution.sample(&mut rand::rng())].0)
+}
+
 pub fn uct(node: &Node, c: f32) -> f32 {
     let stats = node.stats();
 
@@ -97,3 +197,31 @@ pub fn isuctc(node: &Node, c_1: f32, c_2: f32) -> f32 {
 
     u + c_1 + ((n + c_2 + 1.0) / c_2).ln() * v
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn child(to: u8, visits: usize, reward: f32) -> (Move, NodeStats) {
+        let mut stats = NodeStats::new(0, 0.0);
+        stats.visits = visits;
+        stats.reward = reward;
+
+        (
+            Move {
+                from: 0,
+                to,
+                ..Move::default()
+            },
+            stats,
+        )
+    }
+
+    #[test]
+    fn lcb_skips_unvisited_children() {
+        let children = [child(1, 0, 0.0), child(2, 1, -0.5)];
+
+        assert_eq!(lcb(&children[0].1, 1, 1.0), f32::NEG_INFINITY);
+        assert_eq!(FinalSelect::LCB(1.0).get(&children), Some(children[1].0));
+    }
+}
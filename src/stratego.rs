@@ -1,19 +1,42 @@
+mod army;
 mod attacks;
+mod error;
+mod event;
 mod information;
 mod moves;
+mod observation;
+mod perft;
 mod position;
+mod record;
+mod render;
+mod rules;
 mod util;
+mod variant;
 
-pub use attacks::{chebyshev, orthogonal, ranged};
+pub use army::Army;
+pub use attacks::{adjacent, chebyshev, orthogonal, ranged, sliding};
+pub use error::{MoveParseError, ParseError, PerftError, RecordParseError};
+pub use event::{Event, EventKind};
 pub use moves::{Move, MoveList, MoveStack};
-pub use position::Position;
-pub use util::{flip_bb, Flag, Piece};
+pub use observation::Observation;
+pub use perft::{divide, perft, perft_checked, PerftCase, PERFT_SUITE};
+pub use position::{Position, PositionUndo};
+pub use record::{GameRecord, Outcome};
+pub use rules::{MoveLimit, Rules};
+pub use util::{
+    compact, expand, file, flip_bb, parse_square, rank, square, square_notation, Flag, Piece, WIDTH,
+};
+pub use variant::Variant;
 
 use crate::bitboard_loop;
-use information::InformationSet;
-use rand::seq::SliceRandom;
+use bincode::{Decode, Encode};
+pub use information::Beliefs;
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+use information::{InformationSet, InformationUndo};
+use rand::{seq::IndexedRandom, Rng};
+use std::cmp::Ordering;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Encode, Decode)]
 pub enum GameState {
     #[default]
     Ongoing,
@@ -22,11 +45,57 @@ pub enum GameState {
     Loss,
 }
 
-#[derive(Clone)]
+/// Reason, why a game has ended
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub enum Termination {
+    FlagCaptured,
+    /// One side has only immovable pieces left
+    NoMovablePieces,
+    /// Side to move has movable pieces, but all moves are forbidden by the rules
+    NoLegalMoves,
+    MoveLimit,
+    /// Both sides have only immovable pieces left
+    BothImmovable,
+}
+
+impl std::fmt::Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            Termination::FlagCaptured => "flag captured",
+            Termination::NoMovablePieces => "no movable pieces",
+            Termination::NoLegalMoves => "no legal moves",
+            Termination::MoveLimit => "move limit",
+            Termination::BothImmovable => "both sides immovable",
+        };
+
+        write!(f, "{reason}")
+    }
+}
+
+/// Final state of a game from the pov of the side to move and the reason it has ended
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct GameResult {
+    pub state: GameState,
+    pub termination: Termination,
+}
+
+#[derive(Clone, Copy, Encode, Decode)]
+struct Undo {
+    mov: Move,
+    board: PositionUndo,
+    info: InformationUndo,
+    events: usize,
+    /// Whether the observation revealed the rank of the attacked piece
+    revealed: bool,
+}
+
+#[derive(Clone, Encode, Decode)]
 pub struct StrategoState {
     board: Position,
     stack: MoveStack,
     info: InformationSet,
+    history: Vec<Undo>,
+    events: Vec<Event>,
 }
 
 impl std::fmt::Display for StrategoState {
@@ -35,19 +104,93 @@ impl std::fmt::Display for StrategoState {
     }
 }
 
+impl std::str::FromStr for StrategoState {
+    type Err = ParseError;
+
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        Ok(StrategoState::new(notation.parse()?))
+    }
+}
+
+impl TryFrom<&str> for StrategoState {
+    type Error = ParseError;
+
+    fn try_from(notation: &str) -> Result<Self, Self::Error> {
+        notation.parse()
+    }
+}
+
 impl StrategoState {
     const PIECES: usize = 7;
     const BOARD: usize = 64;
     pub const FEATURES: usize = StrategoState::BOARD * StrategoState::PIECES * 2;
 
     pub fn from(notation: &str) -> Self {
-        let board = Position::from(notation);
+        notation.parse().unwrap()
+    }
 
-        Self {
+    /// Notation of the position followed by the complete state encoded with bincode as hex
+    ///
+    /// Unlike the notation, it restores the history, the hashes of the previous positions,
+    /// the state of the two-squares and more-squares rules, the information set and the
+    /// events, so a game can be continued exactly from it.
+    pub fn extended(&self) -> String {
+        let bytes = bincode::encode_to_vec(self, bincode::config::standard()).unwrap();
+        let encoded: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+
+        format!("{} {encoded}", self.board.notation())
+    }
+
+    /// Parses the extended notation, whose position must match the encoded state
+    pub fn from_extended(notation: &str) -> Result<Self, ParseError> {
+        let invalid = |reason: &str| ParseError::InvalidEncoding(reason.to_string());
+
+        let (position, encoded) = notation
+            .trim()
+            .rsplit_once(' ')
+            .ok_or_else(|| invalid("missing"))?;
+
+        if encoded.len() % 2 != 0 {
+            return Err(invalid("odd number of digits"));
+        }
+
+        let bytes = (0..encoded.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(encoded.get(i..i + 2)?, 16).ok())
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| invalid("not hexadecimal"))?;
+
+        let (state, _): (Self, usize) =
+            bincode::decode_from_slice(&bytes, bincode::config::standard())
+                .map_err(|err| invalid(&err.to_string()))?;
+
+        let expected = position
+            .split_ascii_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if state.board.notation() != expected {
+            return Err(ParseError::StateMismatch(state.board.notation()));
+        }
+
+        Ok(state)
+    }
+
+    /// Parses a state, whose pieces belong to `army` instead of the default army
+    pub fn with_army(notation: &str, army: Army) -> Result<Self, ParseError> {
+        Ok(StrategoState::new(Position::with_army(notation, army)?))
+    }
+
+    fn new(board: Position) -> Self {
+        let mut pos = Self {
             board,
             stack: MoveStack::default(),
             info: InformationSet::from(&board),
-        }
+            history: Vec::new(),
+            events: Vec::new(),
+        };
+
+        pos.terminate();
+        pos
     }
 
     pub fn board(&self) -> Position {
@@ -62,17 +205,207 @@ impl StrategoState {
         self.board.gen(&self.stack)
     }
 
+    /// Parses a move in the notation of the README against the current position
+    ///
+    /// The rank prefix of the last capture is ignored, the rank suffix must match the moved
+    /// piece, but can be omitted. Illegal moves are rejected with the reason.
+    pub fn parse_move(&self, notation: &str) -> Result<Move, MoveParseError> {
+        let invalid = || MoveParseError::InvalidNotation(notation.to_string());
+
+        let mov_str = match notation.split_once('x') {
+            Some((prefix, rest)) if Piece::from_rank(prefix).is_some() => rest,
+            _ => notation,
+        };
+
+        let (squares, rank) = match mov_str.split_once('x') {
+            Some((squares, rank)) => (squares, Some(rank)),
+            None => (mov_str, None),
+        };
+
+        let variant = self.board.variant();
+        let on_board = |sq: u8| (variant.squares() & (1 << sq)) != 0;
+
+        let (from, rest) = parse_square(squares).ok_or_else(invalid)?;
+        let (to, rest) = parse_square(rest).ok_or_else(invalid)?;
+        if !rest.is_empty() || !on_board(from) || !on_board(to) {
+            return Err(invalid());
+        }
+
+        let piece = match rank {
+            Some(rank) => Some(Piece::from_rank(rank).ok_or_else(invalid)?),
+            None => None,
+        };
+
+        let moves = self.gen();
+        let Some(mov) = moves.iter().find(|mov| mov.from == from && mov.to == to) else {
+            return Err(self.illegal(from, to, piece));
+        };
+
+        if let Some(piece) = piece {
+            if piece != mov.piece as usize {
+                return Err(MoveParseError::RankMismatch {
+                    sq: from,
+                    rank: Piece::rank(piece),
+                });
+            }
+
+            if (mov.flag & Flag::CAPTURE) == 0 {
+                return Err(MoveParseError::NoCapture(to));
+            }
+        }
+
+        Ok(mov)
+    }
+
     pub fn make(&mut self, mov: Move) {
-        self.info.update(&mov, &self.board);
-        self.board.make(&mov);
-        self.stack.push(self.board.hash());
+        let events = self.events.len();
+        self.log(&mov);
+
+        let info = self.info.update(&mov, &self.board);
+        let board = self.board.make(&mov);
+        self.stack.push(self.board.hash(), self.board.mirrored());
+
+        self.history.push(Undo {
+            mov,
+            board,
+            info,
+            events,
+            revealed: false,
+        });
+        self.terminate();
     }
 
+    /// Ends the game, if the side to move can't move or the move limit is reached
+    ///
+    /// The result is restored by unmaking the move of the board.
+    fn terminate(&mut self) {
+        if self.game_over() {
+            return;
+        }
+
+        if !self.board.has_moves(&self.stack) {
+            self.board.end(GameState::Loss, Termination::NoLegalMoves);
+        } else if self.rules().limit.reached(self.ply(), self.board.half()) {
+            self.board.end(GameState::Draw, Termination::MoveLimit);
+        }
+    }
+
+    /// Appends the public events of `mov`, which is made in the current position
+    fn log(&mut self, mov: &Move) {
+        let ply = self.ply();
+        let stm = usize::from(self.stm());
+
+        if (mov.flag & Flag::CAPTURE) != 0 {
+            let attacker = mov.piece as usize;
+            let defender = self.board.piece(mov.to);
+            let outcome = self.rules().combat(attacker, defender);
+
+            let mut events = vec![(
+                stm,
+                EventKind::Combat {
+                    from: mov.from,
+                    to: mov.to,
+                    attacker,
+                    defender,
+                    outcome,
+                },
+            )];
+
+            if outcome != Ordering::Greater {
+                events.push((
+                    stm,
+                    EventKind::Captured {
+                        square: mov.to,
+                        piece: attacker,
+                    },
+                ));
+            }
+
+            if outcome != Ordering::Less {
+                events.push((
+                    stm ^ 1,
+                    EventKind::Captured {
+                        square: mov.to,
+                        piece: defender,
+                    },
+                ));
+            }
+
+            self.events.extend(
+                events
+                    .into_iter()
+                    .map(|(side, kind)| Event { ply, side, kind }),
+            );
+        } else if (self.info.get(stm) & (1u128 << mov.from)) != 0 {
+            let (from, to) = (mov.from, mov.to);
+            let kind = if (adjacent(from as usize) & (1u128 << to)) != 0 {
+                EventKind::Moved { from, to }
+            } else {
+                EventKind::Scout { from, to }
+            };
+
+            self.events.push(Event {
+                ply,
+                side: stm,
+                kind,
+            });
+        }
+    }
+
+    /// Public events of the game in the order they occurred
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Public events, which were caused by moves of `side` or concern its pieces
+    pub fn events_of(&self, side: usize) -> impl Iterator<Item = &Event> {
+        self.events.iter().filter(move |event| event.side == side)
+    }
+
+    /// Takes back the last move and returns it, if any move was made
+    pub fn unmake(&mut self) -> Option<Move> {
+        let undo = self.history.pop()?;
+
+        self.stack.pop();
+        self.board.unmake(&undo.mov, &undo.board);
+        self.info.revert(&undo.mov, &self.board, &undo.info);
+        self.events.truncate(undo.events);
+
+        // Hides the attacked piece again, so the observation is the same as before the move
+        if undo.revealed {
+            let stm = usize::from(self.stm());
+            let piece = self.board.piece(undo.mov.to);
+
+            self.board.toggle(stm ^ 1, piece, undo.mov.to);
+            self.board.toggle(stm ^ 1, Piece::UNKNOWN, undo.mov.to);
+        }
+
+        Some(undo.mov)
+    }
+
+    /// Takes back moves until only `ply` moves are left
+    pub fn rewind(&mut self, ply: usize) {
+        while self.history.len() > ply {
+            self.unmake();
+        }
+    }
+
+    /// Moves made since the start position
+    pub fn history(&self) -> Vec<Move> {
+        self.history.iter().map(|undo| undo.mov).collect()
+    }
+
+    /// Number of moves, which can be taken back
+    pub fn ply(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Input features of the value network, which is only trained on Quick Battle
     pub fn features<const STM: usize>(&self) -> [f32; StrategoState::FEATURES] {
         let mut features = [0f32; StrategoState::FEATURES];
 
-        let us_bb = self.board.get(STM);
-        let them_bb = self.board.get(STM ^ 1);
+        let us_bb = self.board.compact(STM);
+        let them_bb = self.board.compact(STM ^ 1);
         for i in 0..StrategoState::PIECES {
             let piece = match i {
                 0 => Piece::FLAG,
@@ -85,7 +418,7 @@ impl StrategoState {
                 _ => unreachable!(),
             };
 
-            let pieces = self.board.get(piece);
+            let pieces = self.board.compact(piece);
 
             let mut us_bb = us_bb & pieces;
             let mut them_bb = them_bb & pieces;
@@ -113,43 +446,213 @@ impl StrategoState {
         features
     }
 
+    /// Features of the mirrored state, which are mirrored between left and right in each
+    /// plane of `features::<STM>()`
+    pub fn mirror_features(
+        features: &[f32; StrategoState::FEATURES],
+    ) -> [f32; StrategoState::FEATURES] {
+        std::array::from_fn(|i| {
+            let (plane, sq) = (i / StrategoState::BOARD, i % StrategoState::BOARD);
+
+            features[plane * StrategoState::BOARD + (sq ^ 7)]
+        })
+    }
+
+    /// State mirrored between the left and right side of the board, including its history
+    pub fn mirror(&self) -> Self {
+        let variant = self.board.variant();
+
+        Self {
+            board: self.board.mirror(),
+            stack: self.stack.mirror(),
+            info: self.info.mirror(variant),
+            history: self
+                .history
+                .iter()
+                .map(|undo| Undo {
+                    mov: undo.mov.mirror(variant),
+                    board: undo.board.mirror(variant),
+                    ..*undo
+                })
+                .collect(),
+            events: self
+                .events
+                .iter()
+                .map(|event| event.mirror(variant))
+                .collect(),
+        }
+    }
+
+    /// Probabilities of the ranks of the opponent's hidden pieces from the view of `side`
+    pub fn beliefs(&self, side: usize) -> Beliefs {
+        self.info.beliefs(side ^ 1)
+    }
+
+    /// Beliefs of `side` reweighted by a behaviour model, which gives the likelihood of an
+    /// event of a hidden piece given its rank
+    ///
+    /// Each piece is reweighted on its own, so the expected counts of the ranks are only
+    /// approximately kept.
+    pub fn weighted_beliefs(&self, side: usize, model: impl Fn(&Event, usize) -> f32) -> Beliefs {
+        let mut beliefs = self.info.beliefs(side ^ 1);
+        let likelihoods = self.likelihoods(side, model);
+
+        for (row, likelihood) in beliefs.iter_mut().zip(likelihoods.iter()) {
+            row.iter_mut()
+                .zip(likelihood)
+                .for_each(|(p, likelihood)| *p *= likelihood);
+
+            let sum: f32 = row.iter().sum();
+            if sum > 0.0 {
+                row.iter_mut().for_each(|p| *p /= sum);
+            }
+        }
+
+        beliefs
+    }
+
+    /// Likelihood of the logged events of each hidden piece of the opponent of `side` given
+    /// its rank, where pieces are followed along their moves
+    pub fn likelihoods(&self, side: usize, model: impl Fn(&Event, usize) -> f32) -> Beliefs {
+        let stm = side ^ 1;
+        let mut likelihoods = [[0f32; 15]; 100];
+
+        let mut trails: Vec<Vec<&Event>> = vec![Vec::new(); 100];
+        for event in self.events_of(stm) {
+            if let EventKind::Moved { from, to } = event.kind {
+                let mut trail = std::mem::take(&mut trails[from as usize]);
+                trail.push(event);
+                trails[to as usize] = trail;
+            }
+        }
+
+        let mut hidden = self.info.get(stm);
+        bitboard_loop!(hidden, sq, {
+            let trail = &trails[sq as usize];
+
+            for (piece, likelihood) in likelihoods[sq as usize].iter_mut().enumerate() {
+                *likelihood = trail.iter().map(|event| model(event, piece)).product();
+            }
+        });
+
+        likelihoods
+    }
+
+    /// Random assignment of the hidden pieces, which respects the deduced ranks
     pub fn determination(&self) -> Self {
         let mut pos = self.clone();
         let mut rng = rand::rng();
 
-        let mut red = pos.info.available_immovable(0);
-        let mut blue = pos.info.available_immovable(1);
+        for stm in 0..2 {
+            let mut pieces = self.info.available(stm);
+            pieces.extend(self.info.available_immovable(stm));
 
-        red.shuffle(&mut rng);
-        blue.shuffle(&mut rng);
-
-        let unknown = pos.board.get(Piece::UNKNOWN) & self.info.initial(0) & pos.board.get(0);
-        if unknown != 0 {
-            StrategoState::determinize_bb(&mut pos, 0, unknown, &mut red);
+            let deductions = self.info.deductions(stm);
+            for sq in StrategoState::constrained(&pos.board, stm, &deductions) {
+                pos.determinize(stm, sq, deductions[sq as usize], &mut pieces, &mut rng);
+            }
         }
 
-        let unknown = pos.board.get(Piece::UNKNOWN) & self.info.initial(1) & pos.board.get(1);
-        if unknown != 0 {
-            StrategoState::determinize_bb(&mut pos, 1, unknown, &mut blue);
-        }
+        // Pieces of unknown rank were assumed to be movable
+        pos.terminate();
+        pos
+    }
+
+    /// All distinct assignments of the hidden pieces, which are consistent with the
+    /// information, or `None` if there are more than `limit` of them
+    ///
+    /// Each hidden piece is only assigned its deduced ranks, e.g. bombs and the flag are only
+    /// assigned to unmoved pieces. The worlds aren't weighted by the likelihood of the
+    /// opponent's moves.
+    pub fn determinizations(&self, limit: usize) -> Option<Vec<Self>> {
+        let mut worlds = vec![self.clone()];
+
+        for stm in 0..2 {
+            let unknown = self.board.get(Piece::UNKNOWN) & self.board.get(stm);
+            if unknown == 0 {
+                continue;
+            }
 
-        let mut red = pos.info.available(0);
-        let mut blue = pos.info.available(1);
+            let mut counts = [0usize; 15];
+            self.info
+                .available(stm)
+                .into_iter()
+                .chain(self.info.available_immovable(stm))
+                .for_each(|piece| counts[piece] += 1);
 
-        red.shuffle(&mut rng);
-        blue.shuffle(&mut rng);
+            // Ranks of pieces outside of the army can't be enumerated
+            if counts.iter().sum::<usize>() != unknown.count_ones() as usize {
+                return None;
+            }
 
-        let unknown = pos.board.get(Piece::UNKNOWN) & pos.board.get(0);
-        if unknown != 0 {
-            StrategoState::determinize_bb(&mut pos, 0, unknown, &mut red);
+            let deductions = self.info.deductions(stm);
+            let squares: Vec<_> = StrategoState::constrained(&self.board, stm, &deductions)
+                .into_iter()
+                .map(|sq| (sq, deductions[sq as usize]))
+                .collect();
+
+            let mut assignments = Vec::new();
+            StrategoState::assign(
+                &squares,
+                &mut counts,
+                &mut Vec::new(),
+                &mut assignments,
+                limit / worlds.len(),
+            )?;
+
+            worlds = worlds
+                .iter()
+                .flat_map(|world| {
+                    assignments.iter().map(|pieces| {
+                        let mut world = world.clone();
+
+                        for (&(sq, _), &piece) in squares.iter().zip(pieces) {
+                            world.board.toggle(stm, Piece::UNKNOWN, sq);
+                            world.board.toggle(stm, piece, sq);
+                        }
+
+                        world
+                    })
+                })
+                .collect();
         }
 
-        let unknown = pos.board.get(Piece::UNKNOWN) & pos.board.get(1);
-        if unknown != 0 {
-            StrategoState::determinize_bb(&mut pos, 1, unknown, &mut blue);
+        // Pieces of unknown rank were assumed to be movable
+        worlds.iter_mut().for_each(|world| world.terminate());
+        Some(worlds)
+    }
+
+    /// Adds all assignments of `counts` to the remaining `squares` with their possible ranks
+    /// to `assignments`, which fails as soon as there are more than `limit`
+    fn assign(
+        squares: &[(u8, u16)],
+        counts: &mut [usize; 15],
+        current: &mut Vec<usize>,
+        assignments: &mut Vec<Vec<usize>>,
+        limit: usize,
+    ) -> Option<()> {
+        let Some(&(_, candidates)) = squares.get(current.len()) else {
+            assignments.push(current.clone());
+            return (assignments.len() <= limit).then_some(());
+        };
+
+        for piece in Piece::FLAG..=Piece::BOMB {
+            if counts[piece] == 0 || (candidates & (1 << piece)) == 0 {
+                continue;
+            }
+
+            counts[piece] -= 1;
+            current.push(piece);
+
+            let result = StrategoState::assign(squares, counts, current, assignments, limit);
+
+            current.pop();
+            counts[piece] += 1;
+
+            result?;
         }
 
-        pos
+        Some(())
     }
 
     pub fn anonymize(&self, stm: usize) -> Self {
@@ -166,12 +669,75 @@ impl StrategoState {
         pos
     }
 
+    /// View of the game for `side`, in which the hidden pieces of the opponent are unknown
+    ///
+    /// The game is replayed from its start, so that the hashes of all positions and the
+    /// moves only hold information, which is visible to `side`. An observation, which is
+    /// kept up to date by `Observation::update`, only replays the new moves instead.
+    pub fn observe(&self, side: usize) -> Observation {
+        let mut start = self.clone();
+        start.rewind(0);
+
+        let mut observation = Observation::new(side, start.anonymize(side ^ 1));
+        observation.update(self);
+        observation
+    }
+
+    /// Observation of a cheating side to move, which also holds the hidden information
+    pub fn observe_all(&self) -> Observation {
+        Observation::new(usize::from(self.stm()), self.clone())
+    }
+
+    /// Makes a move as seen on the board of the observation, which reveals the ranks of both
+    /// pieces of a capture and hides the rank of an unknown piece unless it moves like a scout
+    ///
+    /// `defender` is the rank of the attacked piece, if the move is a capture.
+    fn make_observed(&mut self, mut mov: Move, defender: usize) {
+        let stm = usize::from(self.stm());
+        let mut revealed = false;
+
+        if (mov.flag & Flag::CAPTURE) != 0 {
+            if self.board.piece(mov.to) == Piece::UNKNOWN {
+                self.board.toggle(stm ^ 1, Piece::UNKNOWN, mov.to);
+                self.board.toggle(stm ^ 1, defender, mov.to);
+                revealed = true;
+            }
+        } else if self.board.piece(mov.from) == Piece::UNKNOWN {
+            mov.piece = if (adjacent(mov.from as usize) & (1u128 << mov.to)) != 0 {
+                Piece::UNKNOWN as u8
+            } else {
+                Piece::SCOUT as u8
+            };
+        }
+
+        self.make(mov);
+        self.history.last_mut().unwrap().revealed = revealed;
+    }
+
     pub fn game_state(&self) -> GameState {
         self.board.game_state()
     }
 
-    pub fn set_game_state(&mut self, state: GameState) {
-        self.board.set_game_state(state);
+    /// Result of the game, if it has ended
+    pub fn result(&self) -> Option<GameResult> {
+        self.board.result()
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.board.rules()
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.board.set_rules(rules);
+        self.terminate();
+    }
+
+    /// Draws the game after `limit`, which replaces the limit of the rules
+    pub fn set_limit(&mut self, limit: MoveLimit) {
+        let mut rules = self.rules();
+        rules.limit = limit;
+
+        self.set_rules(rules);
     }
 
     pub fn hash(&self) -> u64 {
@@ -186,16 +752,250 @@ impl StrategoState {
         self.board.game_over()
     }
 
-    fn determinize_bb(pos: &mut StrategoState, stm: usize, bb: u64, pieces: &mut Vec<usize>) {
-        let mut bb = bb;
+    /// Reason, why the move from `from` to `to` isn't generated
+    fn illegal(&self, from: u8, to: u8, expected: Option<usize>) -> MoveParseError {
+        let board = &self.board;
+        let stm = board.stm() as usize;
 
-        bitboard_loop!(bb, sq, {
-            if let Some(new) = pieces.pop() {
-                pos.board.toggle(stm, Piece::UNKNOWN, sq);
-                pos.board.toggle(stm, new, sq);
+        if board.game_over() {
+            return MoveParseError::GameOver;
+        }
+
+        let from_bb = 1u128 << from;
+        let to_bb = 1u128 << to;
+        let occupied = board.get(0) | board.get(1);
+
+        if (occupied & from_bb) == 0 {
+            return MoveParseError::EmptySquare(from);
+        }
+
+        if (board.get(stm) & from_bb) == 0 {
+            return MoveParseError::WrongSide(from);
+        }
+
+        let piece = board.piece(from);
+        if !(Piece::SPY..=Piece::MARSHAL).contains(&piece) {
+            return MoveParseError::Immovable(from);
+        }
+
+        if let Some(expected) = expected.filter(|&expected| expected != piece) {
+            return MoveParseError::RankMismatch {
+                sq: from,
+                rank: Piece::rank(expected),
+            };
+        }
+
+        let distance = file(from).abs_diff(file(to)) + rank(from).abs_diff(rank(to));
+        let straight = file(from) == file(to) || rank(from) == rank(to);
+        if distance == 0 || !straight || (piece != Piece::SCOUT && distance > 1) {
+            return MoveParseError::Unreachable { from, to };
+        }
+
+        let path = attacks::between_squares(from, to) & !(from_bb | to_bb);
+
+        // Square of the path, which is closest to the moving piece
+        let nearest = |bb: u128| {
+            if from < to {
+                bb.trailing_zeros() as u8
+            } else {
+                127 - bb.leading_zeros() as u8
             }
-        });
+        };
+
+        let lakes = board.variant().lakes() & (path | to_bb);
+        if lakes != 0 {
+            return MoveParseError::Lake(nearest(lakes));
+        }
+
+        if (occupied & path) != 0 {
+            return MoveParseError::Blocked(nearest(occupied & path));
+        }
+
+        if (board.get(stm) & to_bb) != 0 {
+            return MoveParseError::OwnPiece(to);
+        }
+
+        if distance > 1 && !board.rules().scout_strikes && (occupied & to_bb) != 0 {
+            return MoveParseError::Unreachable { from, to };
+        }
+
+        if board.last(stm) == Some(from) && (board.two_squares(stm) & to_bb) != 0 {
+            return MoveParseError::TwoSquares;
+        }
 
-        assert!(pieces.len() == 0);
+        MoveParseError::MoreSquares
+    }
+
+    /// Unknown pieces of `stm` on `board`, the ones with the fewest `deductions` first
+    ///
+    /// Assigning them in this order doesn't use up the ranks, which more constrained pieces
+    /// need, e.g. moved pieces come before unmoved ones.
+    fn constrained(board: &Position, stm: usize, deductions: &[u16; 100]) -> Vec<u8> {
+        let mut squares = Vec::new();
+
+        let mut unknown = board.get(Piece::UNKNOWN) & board.get(stm);
+        bitboard_loop!(unknown, sq, squares.push(sq));
+
+        squares.sort_by_key(|&sq| deductions[sq as usize].count_ones());
+        squares
+    }
+
+    /// Assigns a random one of `pieces`, which the unknown piece on `sq` can be
+    fn determinize(
+        &mut self,
+        stm: usize,
+        sq: u8,
+        candidates: u16,
+        pieces: &mut Vec<usize>,
+        rng: &mut impl Rng,
+    ) {
+        let matching = (0..pieces.len())
+            .filter(|&i| (candidates & (1 << pieces[i])) != 0)
+            .collect::<Vec<_>>();
+
+        if let Some(&i) = matching.choose(rng) {
+            self.board.toggle(stm, Piece::UNKNOWN, sq);
+            self.board.toggle(stm, pieces.swap_remove(i), sq);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deployment::random;
+
+    /// Everything, which unmaking a move has to restore
+    #[derive(Clone, PartialEq)]
+    struct Snapshot {
+        board: Position,
+        hash: u64,
+        mirrored: u64,
+        stack: MoveStack,
+        info: InformationSet,
+        game_state: GameState,
+        events: usize,
+    }
+
+    impl Snapshot {
+        fn of(pos: &StrategoState) -> Self {
+            Self {
+                board: pos.board,
+                hash: pos.board.hash(),
+                mirrored: pos.board.mirrored(),
+                stack: pos.stack.clone(),
+                info: pos.info,
+                game_state: pos.game_state(),
+                events: pos.events.len(),
+            }
+        }
+    }
+
+    fn random_start(army: Army) -> StrategoState {
+        let red = random(&army).to_ascii_uppercase();
+        let red = red.split('/').rev().collect::<Vec<_>>().join("/");
+        let blue = random(&army);
+
+        GameRecord::new("red", "blue", [red, blue]).start().unwrap()
+    }
+
+    #[test]
+    fn make_unmake_round_trip() {
+        let mut rng = rand::rng();
+
+        for army in [Army::QUICK_BATTLE, Army::CLASSIC].repeat(5) {
+            let mut pos = random_start(army);
+            let mut snapshots = vec![Snapshot::of(&pos)];
+
+            while !pos.game_over() && pos.ply() < 400 {
+                let moves = pos.gen();
+
+                // Every legal move is taken back exactly
+                for mov in moves.iter() {
+                    pos.make(mov);
+                    pos.unmake();
+
+                    assert!(
+                        Snapshot::of(&pos) == snapshots[pos.ply()],
+                        "{mov} at ply {} of {pos}",
+                        pos.ply()
+                    );
+                }
+
+                pos.make(moves[rng.random_range(0..moves.len())]);
+                snapshots.push(Snapshot::of(&pos));
+            }
+
+            let plies = pos.ply();
+            let mut replayed = pos.clone();
+
+            while pos.unmake().is_some() {
+                assert!(
+                    Snapshot::of(&pos) == snapshots[pos.ply()],
+                    "unmake to ply {} of {pos}",
+                    pos.ply()
+                );
+            }
+
+            replayed.rewind(plies / 2);
+            assert!(Snapshot::of(&replayed) == snapshots[plies / 2]);
+
+            replayed.rewind(0);
+            assert!(Snapshot::of(&replayed) == snapshots[0]);
+        }
+    }
+
+    #[test]
+    fn observation_leaks_no_hidden_ranks() {
+        let mut rng = rand::rng();
+
+        // Flag and bomb as well as marshal and general of red are swapped
+        let start = [
+            "d2f4/bbg4c/1m3dsc/8/8/BD3M1G/F5SD/1BCC4 r",
+            "d2f4/bbg4c/1m3dsc/8/8/FD3G1M/B5SD/1BCC4 r",
+        ];
+
+        for _ in 0..20 {
+            let mut games = start.map(StrategoState::from);
+            let mut observations = games.clone().map(|game| game.observe(1));
+
+            while !games[0].game_over() && games[0].ply() < 200 {
+                for (game, observation) in games.iter().zip(observations.iter_mut()) {
+                    observation.update(game);
+
+                    let fresh = game.observe(1);
+                    assert!(Snapshot::of(observation.state()) == Snapshot::of(fresh.state()));
+                }
+
+                let [a, b] = &observations;
+                assert!(Snapshot::of(a.state()) == Snapshot::of(b.state()), "{a}");
+                assert_eq!(a.events(), b.events());
+                for piece in Piece::FLAG..=Piece::BOMB {
+                    let red = |observation: &Observation| {
+                        observation.board().get(piece) & observation.board().get(0)
+                    };
+                    assert_eq!(red(a), red(b));
+                }
+
+                // Fights of the swapped pieces would reveal them
+                let swapped = |sq: u8| games[0].board.piece(sq) != games[1].board.piece(sq);
+                let moves: Vec<_> = games[0]
+                    .gen()
+                    .iter()
+                    .filter(|mov| {
+                        (mov.flag & Flag::CAPTURE) == 0 || !(swapped(mov.from) || swapped(mov.to))
+                    })
+                    .collect();
+                let Some(&mov) = moves.get(rng.random_range(0..moves.len().max(1))) else {
+                    break;
+                };
+
+                let other = games[1].gen().iter().find(|other| {
+                    (other.from, other.to, other.flag) == (mov.from, mov.to, mov.flag)
+                });
+                games[0].make(mov);
+                games[1].make(other.unwrap());
+            }
+        }
     }
 }
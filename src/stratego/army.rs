@@ -0,0 +1,164 @@
+use super::{Piece, Position, Variant};
+use bincode::{Decode, Encode};
+
+/// Pieces each side deploys and the board they are deployed on
+///
+/// Besides the armies of Quick Battle, the classic game and Barrage, armies can be
+/// defined by their symbols, e.g. `FSCCDGMB`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct Army {
+    variant: Variant,
+    counts: [u8; 15],
+}
+
+impl std::fmt::Display for Army {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbols = self
+            .pieces()
+            .into_iter()
+            .map(|piece| Position::symbol(0, piece))
+            .collect::<String>();
+
+        write!(f, "{symbols}")
+    }
+}
+
+impl Army {
+    pub const QUICK_BATTLE: Army = Army::from_counts(
+        Variant::QuickBattle,
+        &[
+            (Piece::FLAG, 1),
+            (Piece::SPY, 1),
+            (Piece::SCOUT, 2),
+            (Piece::MINER, 2),
+            (Piece::GENERAL, 1),
+            (Piece::MARSHAL, 1),
+            (Piece::BOMB, 2),
+        ],
+    );
+
+    pub const CLASSIC: Army = Army::from_counts(
+        Variant::Classic,
+        &[
+            (Piece::FLAG, 1),
+            (Piece::SPY, 1),
+            (Piece::SCOUT, 8),
+            (Piece::MINER, 5),
+            (Piece::SERGEANT, 4),
+            (Piece::LIEUTENANT, 4),
+            (Piece::CAPTAIN, 4),
+            (Piece::MAJOR, 3),
+            (Piece::COLONEL, 2),
+            (Piece::GENERAL, 1),
+            (Piece::MARSHAL, 1),
+            (Piece::BOMB, 6),
+        ],
+    );
+
+    /// Barrage is played with eight pieces on the classic board
+    pub const BARRAGE: Army = Army::from_counts(
+        Variant::Classic,
+        &[
+            (Piece::FLAG, 1),
+            (Piece::SPY, 1),
+            (Piece::SCOUT, 2),
+            (Piece::MINER, 1),
+            (Piece::GENERAL, 1),
+            (Piece::MARSHAL, 1),
+            (Piece::BOMB, 1),
+        ],
+    );
+
+    /// User-defined army, which must have exactly one flag and fit into the start rows
+    pub fn new(variant: Variant, counts: &[(usize, usize)]) -> Option<Self> {
+        let mut army = Army {
+            variant,
+            counts: [0; 15],
+        };
+
+        for &(piece, count) in counts {
+            if !(Piece::FLAG..=Piece::BOMB).contains(&piece) || piece == Piece::UNKNOWN {
+                return None;
+            }
+
+            army.counts[piece] = army.counts[piece].checked_add(u8::try_from(count).ok()?)?;
+        }
+
+        let area = variant.files() * variant.deployment_ranks();
+        if army.count(Piece::FLAG) != 1 || army.len() > area {
+            return None;
+        }
+
+        Some(army)
+    }
+
+    /// Parses an army from the symbols of its pieces in any order and case
+    pub fn parse(variant: Variant, symbols: &str) -> Option<Self> {
+        let counts = symbols
+            .chars()
+            .map(|c| {
+                (Piece::FLAG..=Piece::BOMB)
+                    .find(|&piece| Position::symbol(0, piece) == c.to_ascii_uppercase())
+                    .map(|piece| (piece, 1))
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Army::new(variant, &counts)
+    }
+
+    const fn from_counts(variant: Variant, counts: &[(usize, usize)]) -> Self {
+        let mut army = Army {
+            variant,
+            counts: [0; 15],
+        };
+
+        let mut i = 0;
+        while i < counts.len() {
+            army.counts[counts[i].0] = counts[i].1 as u8;
+            i += 1;
+        }
+
+        army
+    }
+
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    /// Number of pieces of `piece`
+    pub fn count(&self, piece: usize) -> usize {
+        self.counts[piece] as usize
+    }
+
+    /// Number of pieces in total
+    pub fn len(&self) -> usize {
+        self.counts.iter().map(|&count| count as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// All pieces ordered by rank
+    pub fn pieces(&self) -> Vec<usize> {
+        (Piece::FLAG..=Piece::BOMB)
+            .flat_map(|piece| std::iter::repeat_n(piece, self.count(piece)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn too_many_pieces_are_rejected() {
+        let symbols = format!("F{}", "B".repeat(300));
+
+        assert_eq!(Army::parse(Variant::Classic, &symbols), None);
+        assert_eq!(
+            Army::new(Variant::Classic, &[(Piece::FLAG, 1), (Piece::BOMB, 300)]),
+            None
+        );
+    }
+}
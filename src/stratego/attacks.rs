@@ -1,3 +1,5 @@
+use super::util::{file, rank};
+
 macro_rules! init_lookup {
     (| $bb:ident | $($intern:expr)+) => {
         init_lookup!(|64, index| {
@@ -21,6 +23,20 @@ macro_rules! init_lookup {
     }};
 }
 
+const WIDTH: usize = super::util::WIDTH as usize;
+const FILE: u128 = {
+    let mut file = 0u128;
+
+    let mut rank = 0;
+    while rank < WIDTH {
+        file |= 1 << (rank * WIDTH);
+        rank += 1;
+    }
+
+    file
+};
+
+/// Orthogonal neighbours in the 8x8 layout of Quick Battle
 pub fn orthogonal(sq: usize) -> u64 {
     const ATTACKS: [u64; 64] = init_lookup!(|bb| {
         ((bb << 1) & 0xfefefefefefefefe) | ((bb >> 1) & 0x7f7f7f7f7f7f7f7f) | (bb << 8) | (bb >> 8)
@@ -111,17 +127,113 @@ pub fn ranged(sq: usize, occ: u64) -> u64 {
     file | rank
 }
 
-pub fn between_squares(from: u8, to: u8) -> u64 {
+/// Orthogonal neighbours in board layout
+pub fn adjacent(sq: usize) -> u128 {
+    const ATTACKS: [u128; 100] = {
+        let mut attacks = [0u128; 100];
+
+        let mut sq = 0;
+        while sq < 100 {
+            let (file, rank) = (sq % WIDTH, sq / WIDTH);
+
+            if file > 0 {
+                attacks[sq] |= 1 << (sq - 1);
+            }
+            if file < WIDTH - 1 {
+                attacks[sq] |= 1 << (sq + 1);
+            }
+            if rank > 0 {
+                attacks[sq] |= 1 << (sq - WIDTH);
+            }
+            if rank < WIDTH - 1 {
+                attacks[sq] |= 1 << (sq + WIDTH);
+            }
+
+            sq += 1;
+        }
+
+        attacks
+    };
+
+    ATTACKS[sq]
+}
+
+/// Sliding attacks in board layout, which stop at (and include) the first blocker
+pub fn sliding(sq: usize, occ: u128) -> u128 {
+    // Rays to north, east, south and west without the starting square
+    const RAYS: [[u128; 100]; 4] = {
+        let mut rays = [[0u128; 100]; 4];
+
+        let mut sq = 0;
+        while sq < 100 {
+            let (file, rank) = (sq % WIDTH, sq / WIDTH);
+
+            let mut i = rank + 1;
+            while i < WIDTH {
+                rays[0][sq] |= 1 << (i * WIDTH + file);
+                i += 1;
+            }
+
+            let mut i = file + 1;
+            while i < WIDTH {
+                rays[1][sq] |= 1 << (rank * WIDTH + i);
+                i += 1;
+            }
+
+            let mut i = 0;
+            while i < rank {
+                rays[2][sq] |= 1 << (i * WIDTH + file);
+                i += 1;
+            }
+
+            let mut i = 0;
+            while i < file {
+                rays[3][sq] |= 1 << (rank * WIDTH + i);
+                i += 1;
+            }
+
+            sq += 1;
+        }
+
+        rays
+    };
+
+    let mut attacks = 0;
+
+    for ray in &RAYS[..2] {
+        let blocker = ray[sq] & occ;
+
+        attacks |= if blocker != 0 {
+            ray[sq] ^ ray[blocker.trailing_zeros() as usize]
+        } else {
+            ray[sq]
+        };
+    }
+
+    for ray in &RAYS[2..] {
+        let blocker = ray[sq] & occ;
+
+        attacks |= if blocker != 0 {
+            ray[sq] ^ ray[127 - blocker.leading_zeros() as usize]
+        } else {
+            ray[sq]
+        };
+    }
+
+    attacks
+}
+
+pub fn between_squares(from: u8, to: u8) -> u128 {
     let (from, to) = if from < to { (from, to) } else { (to, from) };
 
-    let from_bit = 1u64 << from;
-    let to_bit = 1u64 << to;
+    let from_bit = 1u128 << from;
+    let to_bit = 1u128 << to;
 
-    let mask = if (from & 7) == (to & 7) {
-        let file_mask = 0x0101010101010101 << (from & 7);
+    let mask = if file(from) == file(to) {
+        let file_mask = FILE << file(from);
         (to_bit - from_bit) & file_mask
     } else {
-        let rank_mask = 0xFF << (from & 56);
+        let rank_mask = 0x3FF << (rank(from) * WIDTH as u8);
         (to_bit - from_bit) & rank_mask
     };
 
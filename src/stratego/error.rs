@@ -0,0 +1,245 @@
+use super::{
+    util::{square, square_notation, Piece},
+    Position,
+};
+
+/// Errors of parsing a position from its notation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    /// Side to move is missing after the board
+    MissingSide,
+    /// Side to move is neither `r` nor `b`
+    InvalidSide(String),
+    /// Board has neither 8 (Quick Battle) nor 10 (Classic) ranks
+    InvalidRanks(usize),
+    /// Character is neither a piece symbol nor a digit
+    InvalidSymbol {
+        rank: usize,
+        file: usize,
+        symbol: char,
+    },
+    /// Rank describes more or less squares than the board has files
+    InvalidFiles { rank: usize, files: usize },
+    /// Piece is placed on a lake
+    Lake {
+        rank: usize,
+        file: usize,
+        symbol: char,
+    },
+    /// Side has more pieces of one rank than its army contains
+    PieceCount {
+        side: usize,
+        piece: usize,
+        count: usize,
+        expected: usize,
+    },
+    /// Flag or bomb of a complete army is placed outside of its side's start rows
+    Deployment {
+        rank: usize,
+        file: usize,
+        symbol: char,
+    },
+    /// Encoded state of an extended notation is missing or can't be decoded
+    InvalidEncoding(String),
+    /// Position of the encoded state differs from the notation in front of it
+    StateMismatch(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::MissingSide => write!(f, "missing side to move"),
+            ParseError::InvalidSide(side) => write!(f, "invalid side to move '{side}'"),
+            ParseError::InvalidRanks(ranks) => write!(f, "invalid number of ranks {ranks}"),
+            ParseError::InvalidSymbol { rank, file, symbol } => {
+                write!(
+                    f,
+                    "invalid symbol '{symbol}' on {}",
+                    square_notation(square(*file as u8, *rank as u8))
+                )
+            }
+            ParseError::InvalidFiles { rank, files } => {
+                write!(f, "rank {} has {files} files", rank + 1)
+            }
+            ParseError::Lake { rank, file, symbol } => {
+                write!(
+                    f,
+                    "piece '{symbol}' on lake {}",
+                    square_notation(square(*file as u8, *rank as u8))
+                )
+            }
+            ParseError::PieceCount {
+                side,
+                piece,
+                count,
+                expected,
+            } => {
+                let symbol = if *piece == Piece::UNKNOWN {
+                    "pieces".to_string()
+                } else {
+                    format!("'{}'", Position::symbol(*side, *piece))
+                };
+
+                write!(f, "{count} {symbol} instead of at most {expected}")
+            }
+            ParseError::Deployment { rank, file, symbol } => write!(
+                f,
+                "piece '{symbol}' on {} outside of start rows",
+                square_notation(square(*file as u8, *rank as u8))
+            ),
+            ParseError::InvalidEncoding(reason) => write!(f, "invalid encoded state: {reason}"),
+            ParseError::StateMismatch(notation) => {
+                write!(f, "encoded state is the position '{notation}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Errors of parsing a move against a position, which also explain illegal moves
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MoveParseError {
+    /// Move doesn't follow the notation
+    InvalidNotation(String),
+    /// Game is already over, so no move is legal
+    GameOver,
+    /// There is no piece on the starting square
+    EmptySquare(u8),
+    /// Piece on the starting square belongs to the opponent
+    WrongSide(u8),
+    /// Flag, bomb or piece with unknown rank can't be moved
+    Immovable(u8),
+    /// Rank in the notation differs from the rank of the moved piece
+    RankMismatch { sq: u8, rank: String },
+    /// Target square can't be reached by the piece in one move
+    Unreachable { from: u8, to: u8 },
+    /// Target square or path is blocked by a lake
+    Lake(u8),
+    /// Path is blocked by another piece
+    Blocked(u8),
+    /// Target square is occupied by an own piece
+    OwnPiece(u8),
+    /// Rank suffix denotes a capture, but the target square is empty
+    NoCapture(u8),
+    /// Move is forbidden by the two-squares rule
+    TwoSquares,
+    /// Move is forbidden by the more-squares rule
+    MoreSquares,
+}
+
+impl std::fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoveParseError::InvalidNotation(mov) => write!(f, "invalid notation '{mov}'"),
+            MoveParseError::GameOver => write!(f, "game is over"),
+            MoveParseError::EmptySquare(sq) => write!(f, "no piece on {}", square_notation(*sq)),
+            MoveParseError::WrongSide(sq) => {
+                write!(
+                    f,
+                    "piece on {} belongs to the opponent",
+                    square_notation(*sq)
+                )
+            }
+            MoveParseError::Immovable(sq) => {
+                write!(f, "piece on {} can't be moved", square_notation(*sq))
+            }
+            MoveParseError::RankMismatch { sq, rank } => {
+                write!(
+                    f,
+                    "piece on {} doesn't have rank {rank}",
+                    square_notation(*sq)
+                )
+            }
+            MoveParseError::Unreachable { from, to } => write!(
+                f,
+                "piece on {} can't reach {}",
+                square_notation(*from),
+                square_notation(*to)
+            ),
+            MoveParseError::Lake(sq) => {
+                write!(f, "path is blocked by lake {}", square_notation(*sq))
+            }
+            MoveParseError::Blocked(sq) => {
+                write!(f, "path is blocked by piece on {}", square_notation(*sq))
+            }
+            MoveParseError::OwnPiece(sq) => write!(f, "own piece on {}", square_notation(*sq)),
+            MoveParseError::NoCapture(sq) => {
+                write!(f, "no piece to capture on {}", square_notation(*sq))
+            }
+            MoveParseError::TwoSquares => write!(f, "forbidden by two-squares rule"),
+            MoveParseError::MoreSquares => write!(f, "forbidden by more-squares rule"),
+        }
+    }
+}
+
+impl std::error::Error for MoveParseError {}
+
+/// Errors of parsing a game record
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RecordParseError {
+    /// Header line isn't of the form `[Name "Value"]`
+    InvalidHeader(String),
+    /// Required header is missing
+    MissingHeader(&'static str),
+    /// Header has a value, which can't be interpreted
+    InvalidValue { name: String, value: String },
+    /// Deployments don't form a valid start position
+    Position(ParseError),
+    /// Move is illegal when replaying the game
+    Move { ply: usize, error: MoveParseError },
+    /// Prefix of a move differs from the rank revealed by the previous capture
+    Capture { ply: usize, rank: String },
+    /// Result at the end of the moves differs from the header
+    ResultMismatch(String),
+}
+
+impl std::fmt::Display for RecordParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordParseError::InvalidHeader(line) => write!(f, "invalid header '{line}'"),
+            RecordParseError::MissingHeader(name) => write!(f, "missing header {name}"),
+            RecordParseError::InvalidValue { name, value } => {
+                write!(f, "invalid value '{value}' of header {name}")
+            }
+            RecordParseError::Position(err) => write!(f, "invalid start position: {err}"),
+            RecordParseError::Move { ply, error } => write!(f, "move {}: {error}", ply + 1),
+            RecordParseError::Capture { ply, rank } => {
+                write!(f, "move {}: previous capture didn't reveal {rank}", ply + 1)
+            }
+            RecordParseError::ResultMismatch(result) => {
+                write!(f, "result '{result}' differs from header")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RecordParseError {}
+
+/// Inconsistencies found by a checked perft, `line` are the moves leading to the position
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PerftError {
+    /// Incremental hash differs from the hash computed from scratch
+    Hash {
+        line: String,
+        hash: u64,
+        expected: u64,
+    },
+    /// Taking back the last move of `line` didn't restore the position
+    Unmake { line: String },
+}
+
+impl std::fmt::Display for PerftError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PerftError::Hash {
+                line,
+                hash,
+                expected,
+            } => write!(f, "hash {hash:x} instead of {expected:x} after {line}"),
+            PerftError::Unmake { line } => write!(f, "unmake didn't restore position after {line}"),
+        }
+    }
+}
+
+impl std::error::Error for PerftError {}
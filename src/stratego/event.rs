@@ -0,0 +1,183 @@
+use super::{util::square_notation, Piece, Variant};
+use bincode::{
+    de::Decoder,
+    enc::Encoder,
+    error::{DecodeError, EncodeError},
+    Decode, Encode,
+};
+use std::cmp::Ordering;
+
+/// Public event of a game, which both players have observed
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct Event {
+    /// Number of moves made before the event
+    pub ply: usize,
+    /// Side, which made the move or lost the piece for captures
+    pub side: usize,
+    pub kind: EventKind,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum EventKind {
+    /// Piece of hidden rank moved to an adjacent square
+    Moved { from: u8, to: u8 },
+    /// Piece of hidden rank moved more than one square and revealed itself as scout
+    Scout { from: u8, to: u8 },
+    /// Attack, which revealed the ranks of both pieces, `Greater` if the attacker won
+    Combat {
+        from: u8,
+        to: u8,
+        attacker: usize,
+        defender: usize,
+        outcome: Ordering,
+    },
+    /// Piece was removed from the board after a combat on `square`
+    Captured { square: u8, piece: usize },
+}
+
+impl Event {
+    /// Same event on the board of `variant` mirrored between left and right
+    pub fn mirror(&self, variant: Variant) -> Event {
+        let mirror = |sq: u8| variant.mirror(sq);
+
+        let kind = match self.kind {
+            EventKind::Moved { from, to } => EventKind::Moved {
+                from: mirror(from),
+                to: mirror(to),
+            },
+            EventKind::Scout { from, to } => EventKind::Scout {
+                from: mirror(from),
+                to: mirror(to),
+            },
+            EventKind::Combat {
+                from,
+                to,
+                attacker,
+                defender,
+                outcome,
+            } => EventKind::Combat {
+                from: mirror(from),
+                to: mirror(to),
+                attacker,
+                defender,
+                outcome,
+            },
+            EventKind::Captured { square, piece } => EventKind::Captured {
+                square: mirror(square),
+                piece,
+            },
+        };
+
+        Event { kind, ..*self }
+    }
+}
+
+// `Ordering` has no encoding, so the outcome of a combat is encoded as `i8`
+impl Encode for EventKind {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        match *self {
+            EventKind::Moved { from, to } => (0u8, from, to).encode(encoder),
+            EventKind::Scout { from, to } => (1u8, from, to).encode(encoder),
+            EventKind::Combat {
+                from,
+                to,
+                attacker,
+                defender,
+                outcome,
+            } => (2u8, from, to, attacker, defender, outcome as i8).encode(encoder),
+            EventKind::Captured { square, piece } => (3u8, square, piece).encode(encoder),
+        }
+    }
+}
+
+impl<Context> Decode<Context> for EventKind {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let kind = match u8::decode(decoder)? {
+            0 => EventKind::Moved {
+                from: u8::decode(decoder)?,
+                to: u8::decode(decoder)?,
+            },
+            1 => EventKind::Scout {
+                from: u8::decode(decoder)?,
+                to: u8::decode(decoder)?,
+            },
+            2 => EventKind::Combat {
+                from: u8::decode(decoder)?,
+                to: u8::decode(decoder)?,
+                attacker: usize::decode(decoder)?,
+                defender: usize::decode(decoder)?,
+                outcome: match i8::decode(decoder)? {
+                    -1 => Ordering::Less,
+                    0 => Ordering::Equal,
+                    1 => Ordering::Greater,
+                    _ => return Err(DecodeError::Other("invalid combat outcome")),
+                },
+            },
+            3 => EventKind::Captured {
+                square: u8::decode(decoder)?,
+                piece: usize::decode(decoder)?,
+            },
+            found => {
+                return Err(DecodeError::UnexpectedVariant {
+                    type_name: "EventKind",
+                    allowed: &bincode::error::AllowedEnumVariants::Range { min: 0, max: 3 },
+                    found: found as u32,
+                })
+            }
+        };
+
+        Ok(kind)
+    }
+}
+
+bincode::impl_borrow_decode!(EventKind);
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let side = ["red", "blue"][self.side];
+        write!(f, "{} {side} ", self.ply + 1)?;
+
+        match self.kind {
+            EventKind::Moved { from, to } => write!(
+                f,
+                "moved unknown {}{}",
+                square_notation(from),
+                square_notation(to)
+            ),
+            EventKind::Scout { from, to } => write!(
+                f,
+                "revealed scout {}{}",
+                square_notation(from),
+                square_notation(to)
+            ),
+            EventKind::Combat {
+                from,
+                to,
+                attacker,
+                defender,
+                outcome,
+            } => {
+                let result = match outcome {
+                    Ordering::Greater => "wins",
+                    Ordering::Equal => "ties",
+                    Ordering::Less => "loses",
+                };
+
+                write!(
+                    f,
+                    "attacked {}{} {} against {} {result}",
+                    square_notation(from),
+                    square_notation(to),
+                    Piece::rank(attacker),
+                    Piece::rank(defender),
+                )
+            }
+            EventKind::Captured { square, piece } => write!(
+                f,
+                "lost {} on {}",
+                Piece::rank(piece),
+                square_notation(square)
+            ),
+        }
+    }
+}
@@ -1,25 +1,46 @@
-use super::{util::Flag, Move, Piece, Position};
+use super::{
+    util::{file, rank, Flag},
+    Move, Piece, Position, Variant,
+};
 use crate::bitboard_loop;
+use bincode::{Decode, Encode};
 use std::usize;
 
+/// Probability of each piece for every square of the board, which is indexed by square
+/// and piece
+pub type Beliefs = [[f32; 15]; 100];
+
+/// Masks of the movable and immovable ranks
+const MOVABLE: u16 = ((1 << (Piece::MARSHAL + 1)) - 1) & !((1 << Piece::SPY) - 1);
+const IMMOVABLE: u16 = (1 << Piece::FLAG) | (1 << Piece::BOMB);
+
+/// Changes of an update, which are required to revert it
+#[derive(Clone, Copy, Encode, Decode)]
+pub struct InformationUndo {
+    defender: u8,
+    mover: u8,
+    moved: bool,
+    initial: bool,
+}
+
 /// Keeps track of all legal information,
 /// which can be retrieved from the `StrategoState`
 ///
 /// What moves reveal legal information?
 /// - moves of absolute distance > 1 (Piece must be Scout)
 /// - captures (Piece ranks must be revealed)
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
 pub struct InformationSet {
-    unknown: [[usize; 10]; 2],
-    bb: [u64; 2],
-    initial: [u64; 2],
+    unknown: [[usize; 15]; 2],
+    bb: [u128; 2],
+    initial: [u128; 2],
 }
 
 impl InformationSet {
-    // All pieces are `UNKNOWN`
+    // All pieces are `UNKNOWN`, hidden pieces of a complete army can be any piece of it
     pub fn from(board: &Position) -> Self {
-        let mut unknown = [[0usize; 10]; 2];
-        let mut bb = [0u64; 2];
+        let mut unknown = [[0usize; 15]; 2];
+        let mut bb = [0u128; 2];
 
         for piece in Piece::FLAG..=Piece::BOMB {
             let occ = board.get(piece);
@@ -28,7 +49,15 @@ impl InformationSet {
                 let mut side = board.get(stm) & occ;
 
                 unknown[stm][piece] += side.count_ones() as usize;
-                bitboard_loop!(side, sq, bb[stm] |= 1u64 << sq);
+                bitboard_loop!(side, sq, bb[stm] |= 1u128 << sq);
+            }
+        }
+
+        // Ranks of hidden pieces follow from the army, as long as no piece was captured
+        let army = board.army();
+        for stm in 0..2 {
+            if unknown[stm][Piece::UNKNOWN] > 0 && bb[stm].count_ones() as usize == army.len() {
+                unknown[stm] = std::array::from_fn(|piece| army.count(piece));
             }
         }
 
@@ -39,12 +68,21 @@ impl InformationSet {
         }
     }
 
+    /// Information of the position mirrored between left and right
+    pub fn mirror(&self, variant: Variant) -> Self {
+        InformationSet {
+            bb: self.bb.map(|bb| variant.mirror_bb(bb)),
+            initial: self.initial.map(|bb| variant.mirror_bb(bb)),
+            ..*self
+        }
+    }
+
     pub fn available(&self, stm: usize) -> Vec<usize> {
         self.unknown[stm]
             .iter()
             .enumerate()
-            .skip(3)
-            .take(5)
+            .skip(Piece::SPY)
+            .take(Piece::MARSHAL - Piece::SPY + 1)
             .filter(|(_, &pc)| pc > 0usize)
             .flat_map(|(i, &pc)| std::iter::repeat(i).take(pc))
             .collect()
@@ -61,51 +99,404 @@ impl InformationSet {
         .collect()
     }
 
-    pub fn get(&self, stm: usize) -> u64 {
+    pub fn get(&self, stm: usize) -> u128 {
         self.bb[stm]
     }
 
-    pub fn initial(&self, stm: usize) -> u64 {
+    pub fn initial(&self, stm: usize) -> u128 {
         self.initial[stm]
     }
 
-    pub fn update(&mut self, mov: &Move, board: &Position) {
+    /// Possible ranks of the hidden piece of `stm` on `sq` as mask
+    ///
+    /// Each call deduces the ranks of all hidden pieces, see `deductions`.
+    pub fn candidates(&self, stm: usize, sq: u8) -> u16 {
+        self.deductions(stm)[sq as usize]
+    }
+
+    /// Hidden pieces of `stm`, whose rank is certain
+    pub fn certain(&self, stm: usize) -> Vec<(u8, usize)> {
+        let deductions = self.deductions(stm);
+        let mut certain = Vec::new();
+
+        let mut bb = self.bb[stm];
+        bitboard_loop!(bb, sq, {
+            let candidates = deductions[sq as usize];
+
+            if candidates.count_ones() == 1 {
+                certain.push((sq, candidates.trailing_zeros() as usize));
+            }
+        });
+
+        certain
+    }
+
+    /// Possible ranks of each hidden piece of `stm` as masks, which are indexed by square
+    ///
+    /// Moved pieces can only be movable pieces, the ranks are then narrowed piece by piece
+    /// until nothing changes. If the pieces, which can only be of a rank, take all remaining
+    /// pieces of it, no other piece can be of that rank. If only as many pieces can be of a
+    /// rank as are left of it, all of them are of that rank, e.g. the last unmoved piece is
+    /// the flag. Movable and immovable ranks are narrowed the same way as a whole, so if all
+    /// remaining movable pieces are needed for the moved pieces, every unmoved piece is a bomb
+    /// or the flag.
+    ///
+    /// The deductions are only made on demand, so that `update` stays cheap.
+    pub fn deductions(&self, stm: usize) -> [u16; 100] {
+        let mut deductions = [0u16; 100];
+        let unknown = &self.unknown[stm];
+
+        // Without a complete army, hidden pieces can be of any rank
+        let complete = unknown[Piece::UNKNOWN] == 0;
+
+        let mut bb = self.bb[stm];
+        bitboard_loop!(bb, sq, {
+            let moved = (self.initial[stm] & (1u128 << sq)) == 0;
+
+            deductions[sq as usize] = if moved && complete {
+                MOVABLE
+            } else {
+                MOVABLE | IMMOVABLE
+            };
+        });
+
+        if !complete {
+            return deductions;
+        }
+
+        let groups = (Piece::FLAG..=Piece::BOMB)
+            .filter(|&piece| piece != Piece::UNKNOWN)
+            .map(|piece| 1u16 << piece)
+            .chain([MOVABLE, IMMOVABLE]);
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for group in groups.clone() {
+                let count: usize = (0..15)
+                    .filter(|&piece| (group & (1 << piece)) != 0)
+                    .map(|piece| unknown[piece])
+                    .sum();
+
+                // Pieces, which can only be of the group, and pieces, which can be of it
+                let (mut inside, mut holders) = (0u128, 0u128);
+                let mut bb = self.bb[stm];
+                bitboard_loop!(bb, sq, {
+                    let candidates = deductions[sq as usize];
+
+                    if (candidates & group) != 0 {
+                        holders |= 1u128 << sq;
+
+                        if (candidates & !group) == 0 {
+                            inside |= 1u128 << sq;
+                        }
+                    }
+                });
+
+                if holders == inside {
+                    continue;
+                }
+
+                let (mut narrowed, keep) = if inside.count_ones() as usize == count {
+                    (holders & !inside, !group)
+                } else if holders.count_ones() as usize == count {
+                    (holders, group)
+                } else {
+                    continue;
+                };
+
+                bitboard_loop!(narrowed, sq, deductions[sq as usize] &= keep);
+                changed = true;
+            }
+        }
+
+        deductions
+    }
+
+    /// Probabilities of the ranks of all hidden pieces of `stm`, if each assignment of the
+    /// remaining pieces, which is consistent with the information, is equally likely
+    ///
+    /// These are exactly the marginals of the determinizations. Moved pieces are movable,
+    /// so only the distribution of the movable ranks between moved and unmoved pieces has to
+    /// be counted, which is done with generating functions.
+    pub fn beliefs(&self, stm: usize) -> Beliefs {
+        let mut beliefs = [[0f32; 15]; 100];
+        let unknown = &self.unknown[stm];
+
+        let moved_bb = self.bb[stm] & !self.initial[stm];
+        let unmoved_bb = self.bb[stm] & self.initial[stm];
+        let moved = moved_bb.count_ones() as usize;
+        let unmoved = unmoved_bb.count_ones() as usize;
+
+        let pieces = (Piece::FLAG..=Piece::BOMB).filter(|&piece| piece != Piece::UNKNOWN);
+        let total: usize = pieces.clone().map(|piece| unknown[piece]).sum();
+
+        // Expected number of pieces of each rank on the moved squares
+        let expected = match self.expected_moved(stm, moved) {
+            Some(expected) if total == moved + unmoved => expected,
+            // Without a complete army, pieces are distributed by their counts only
+            _ => {
+                let movable: usize = unknown[Piece::SPY..=Piece::MARSHAL].iter().sum();
+
+                std::array::from_fn(|piece| match piece {
+                    Piece::SPY..=Piece::MARSHAL if movable > 0 => {
+                        (unknown[piece] * moved) as f64 / movable as f64
+                    }
+                    _ => 0.0,
+                })
+            }
+        };
+
+        for piece in pieces {
+            let on_moved = if moved > 0 {
+                expected[piece] / moved as f64
+            } else {
+                0.0
+            };
+            let on_unmoved = if unmoved > 0 {
+                (unknown[piece] as f64 - expected[piece]).max(0.0) / unmoved as f64
+            } else {
+                0.0
+            };
+
+            let mut bb = moved_bb;
+            bitboard_loop!(bb, sq, beliefs[sq as usize][piece] = on_moved as f32);
+
+            let mut bb = unmoved_bb;
+            bitboard_loop!(bb, sq, beliefs[sq as usize][piece] = on_unmoved as f32);
+        }
+
+        // Normalize, if the pieces don't match the hidden pieces
+        if total != moved + unmoved {
+            for row in beliefs.iter_mut() {
+                let sum: f32 = row.iter().sum();
+
+                if sum > 0.0 {
+                    row.iter_mut().for_each(|p| *p /= sum);
+                }
+            }
+        }
+
+        beliefs
+    }
+
+    /// Expected number of pieces of each movable rank of `stm` on the `moved` squares
+    ///
+    /// A split of `k` pieces of a rank with `count` pieces onto the moved squares has weight
+    /// `1 / (k! (count - k)!)`, which is multiplied over all ranks.
+    fn expected_moved(&self, stm: usize, moved: usize) -> Option<[f64; 15]> {
+        let unknown = &self.unknown[stm];
+        let factorial = |n: usize| (1..=n).map(|i| i as f64).product::<f64>();
+
+        let split = |piece: usize, weighted: bool| {
+            let count = unknown[piece];
+
+            (0..=count.min(moved))
+                .map(|k| {
+                    let weight = 1.0 / (factorial(k) * factorial(count - k));
+                    if weighted {
+                        k as f64 * weight
+                    } else {
+                        weight
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let multiply = |a: &[f64], b: &[f64]| {
+            let mut product = vec![0.0; (a.len() + b.len() - 1).min(moved + 1)];
+
+            for (i, x) in a.iter().enumerate() {
+                for (j, y) in b.iter().enumerate().take(product.len().saturating_sub(i)) {
+                    product[i + j] += x * y;
+                }
+            }
+
+            product
+        };
+
+        let coefficient = |except: Option<usize>| {
+            let polynomial = (Piece::SPY..=Piece::MARSHAL).fold(vec![1.0], |product, piece| {
+                multiply(&product, &split(piece, except == Some(piece)))
+            });
+
+            polynomial.get(moved).copied().unwrap_or(0.0)
+        };
+
+        let total = coefficient(None);
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut expected = [0.0; 15];
+        for piece in Piece::SPY..=Piece::MARSHAL {
+            if unknown[piece] > 0 {
+                expected[piece] = coefficient(Some(piece)) / total;
+            }
+        }
+
+        Some(expected)
+    }
+
+    pub fn update(&mut self, mov: &Move, board: &Position) -> InformationUndo {
         let stm = board.stm() as usize;
         let piece = mov.piece as usize;
-        let from_bb = 1u64 << mov.from;
-        let to_bb = 1u64 << mov.to;
+        let from_bb = 1u128 << mov.from;
+        let to_bb = 1u128 << mov.to;
+
+        let mut undo = InformationUndo {
+            defender: 0,
+            mover: 0,
+            moved: false,
+            initial: false,
+        };
 
         if (mov.flag & Flag::CAPTURE) != 0 && (to_bb & self.bb[stm ^ 1]) != 0 {
-            self.remove(stm ^ 1, board.piece(mov.to), mov.to);
+            undo.defender = board.piece(mov.to) as u8;
+            self.remove(stm ^ 1, undo.defender as usize, mov.to);
         }
 
-        if ((1u64 << mov.from) & self.bb[stm]) == 0 {
-            return;
+        if (from_bb & self.bb[stm]) == 0 {
+            return undo;
         }
 
         if (self.initial[stm] & from_bb) != 0 {
             self.initial[stm] ^= from_bb;
+            undo.initial = true;
         }
 
         self.bb[stm] ^= from_bb;
         self.bb[stm] ^= to_bb;
+        undo.moved = true;
 
         if (mov.flag & Flag::CAPTURE) != 0 {
-            self.remove(stm, piece, mov.to);
-        } else if distance(mov.from as i32, mov.to as i32) > 1 {
-            self.remove(stm, Piece::SCOUT, mov.to);
+            undo.mover = piece as u8;
+        } else if distance(mov.from, mov.to) > 1 {
+            undo.mover = Piece::SCOUT as u8;
+        }
+
+        if undo.mover != 0 {
+            self.remove(stm, undo.mover as usize, mov.to);
+        }
+
+        undo
+    }
+
+    /// Reverts `update` with the board from before the move
+    pub fn revert(&mut self, mov: &Move, board: &Position, undo: &InformationUndo) {
+        let stm = board.stm() as usize;
+        let from_bb = 1u128 << mov.from;
+        let to_bb = 1u128 << mov.to;
+
+        if undo.mover != 0 {
+            self.bb[stm] ^= to_bb;
+            self.unknown[stm][undo.mover as usize] += 1;
+        }
+
+        if undo.moved {
+            self.bb[stm] ^= from_bb ^ to_bb;
+        }
+
+        if undo.initial {
+            self.initial[stm] ^= from_bb;
+        }
+
+        if undo.defender != 0 {
+            self.bb[stm ^ 1] ^= to_bb;
+            self.unknown[stm ^ 1][undo.defender as usize] += 1;
         }
     }
 
     pub fn remove(&mut self, stm: usize, piece: usize, sq: u8) {
-        self.bb[stm] ^= 1u64 << sq;
+        self.bb[stm] ^= 1u128 << sq;
         self.unknown[stm][piece] -= 1;
     }
 }
 
-fn distance(from: i32, to: i32) -> usize {
-    let rank = ((from >> 3) - (to >> 3)).abs() as usize;
-    let file = ((from & 7) - (to & 7)).abs() as usize;
+fn distance(from: u8, to: u8) -> usize {
+    let rank = rank(from).abs_diff(rank(to)) as usize;
+    let file = file(from).abs_diff(file(to)) as usize;
 
     rank + file
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() -> (Position, InformationSet) {
+        let board = Position::from("d2f4/bbg4c/1m3dsc/8/8/BD3M1G/F5SD/1BCC4 r");
+        (board, InformationSet::from(&board))
+    }
+
+    /// Reveals the pieces of red, which `reveal` accepts by their square and rank
+    fn reveal(board: &Position, info: &mut InformationSet, reveal: impl Fn(u8, usize) -> bool) {
+        let mut bb = board.get(0);
+        bitboard_loop!(bb, sq, {
+            let piece = board.piece(sq);
+
+            if reveal(sq, piece) {
+                info.remove(0, piece, sq);
+            }
+        });
+    }
+
+    #[test]
+    fn all_movables_revealed() {
+        let (board, mut info) = start();
+        reveal(&board, &mut info, |_, piece| MOVABLE & (1 << piece) != 0);
+
+        let deductions = info.deductions(0);
+        let mut hidden = info.get(0);
+        assert_eq!(hidden.count_ones(), 3);
+        bitboard_loop!(hidden, sq, assert_eq!(deductions[sq as usize], IMMOVABLE));
+        assert!(info.certain(0).is_empty());
+
+        // Last unmoved piece is the flag, once the bombs are revealed too
+        reveal(&board, &mut info, |_, piece| piece == Piece::BOMB);
+
+        let flag = (board.get(0) & board.get(Piece::FLAG)).trailing_zeros() as u8;
+        assert_eq!(info.certain(0), vec![(flag, Piece::FLAG)]);
+    }
+
+    #[test]
+    fn one_unknown_with_one_unaccounted_rank() {
+        let (board, mut info) = start();
+        let miner = (board.get(0) & board.get(Piece::MINER)).trailing_zeros() as u8;
+
+        // One of the miners moves, while every other piece is revealed
+        info.initial[0] ^= 1u128 << miner;
+        reveal(&board, &mut info, |sq, _| sq != miner);
+
+        assert_eq!(info.candidates(0, miner), 1 << Piece::MINER);
+        assert_eq!(info.certain(0), vec![(miner, Piece::MINER)]);
+
+        // Hidden pieces of blue aren't narrowed
+        assert!(info.certain(1).is_empty());
+    }
+
+    #[test]
+    fn deductions_narrow_moved_and_unmoved_pieces() {
+        let (board, mut info) = start();
+        let scout = (board.get(0) & board.get(Piece::SCOUT)).trailing_zeros() as u8;
+
+        // One of the scouts moves, while all other movable pieces are revealed
+        info.initial[0] ^= 1u128 << scout;
+        reveal(&board, &mut info, |sq, piece| {
+            MOVABLE & (1 << piece) != 0 && sq != scout
+        });
+
+        let deductions = info.deductions(0);
+        let mut hidden = info.get(0);
+        bitboard_loop!(hidden, sq, {
+            let expected = if sq == scout {
+                1 << Piece::SCOUT
+            } else {
+                IMMOVABLE
+            };
+
+            assert_eq!(deductions[sq as usize], expected);
+        });
+    }
+}
@@ -1,7 +1,10 @@
-use crate::stratego::util::{Flag, Piece};
+use crate::stratego::{
+    util::{square_notation, Flag, Piece},
+    Variant,
+};
 use bincode::{Decode, Encode};
 
-#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Encode, Decode)]
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash, Debug, Encode, Decode)]
 pub struct Move {
     pub from: u8,
     pub to: u8,
@@ -11,13 +14,6 @@ pub struct Move {
 
 impl std::fmt::Display for Move {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        fn to_notation(sq: u8) -> String {
-            let file = sq & 7;
-            let rank = sq >> 3;
-
-            format!("{}{}", (b'a' + file) as char, rank + 1)
-        }
-
         let suffix = if (self.flag & Flag::CAPTURE) != 0 {
             format!("x{}", Piece::rank(self.piece as usize))
         } else {
@@ -27,13 +23,32 @@ impl std::fmt::Display for Move {
         write!(
             f,
             "{}{}{}",
-            to_notation(self.from),
-            to_notation(self.to),
+            square_notation(self.from),
+            square_notation(self.to),
             suffix
         )
     }
 }
 
+impl Move {
+    /// Same move on the board of `variant` mirrored between left and right
+    pub fn mirror(&self, variant: Variant) -> Move {
+        Move {
+            from: variant.mirror(self.from),
+            to: variant.mirror(self.to),
+            ..*self
+        }
+    }
+
+    /// Whether the move can be the one seen as `observed`, whose piece may be unknown
+    pub fn consistent(&self, observed: &Move) -> bool {
+        self.from == observed.from
+            && self.to == observed.to
+            && (self.flag & Flag::CAPTURE) == (observed.flag & Flag::CAPTURE)
+            && (observed.piece == Piece::UNKNOWN as u8 || self.piece == observed.piece)
+    }
+}
+
 pub struct MoveList {
     moves: Vec<Move>,
 }
@@ -73,45 +88,73 @@ impl MoveList {
     }
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq, Eq, Encode, Decode)]
 pub struct MoveStack {
     stack: Vec<u64>,
+    /// Hashes of the mirrored positions
+    mirrored: Vec<u64>,
 }
 
 impl MoveStack {
-    pub fn repetition(&self, half: usize, current: u64) -> bool {
-        if self.stack.len() < 7 {
-            return false;
-        }
-
-        for &hash in self.stack.iter().rev().take(half + 1).skip(1).step_by(2) {
-            if hash == current {
-                return true;
-            }
-        }
-
-        false
+    /// Checks if the position after a move of the current side occurred during the last `plies`
+    pub fn repeated(&self, plies: usize, hash: u64) -> bool {
+        self.stack
+            .iter()
+            .rev()
+            .take(plies)
+            .skip(1)
+            .step_by(2)
+            .any(|&previous| previous == hash)
     }
 
     pub fn iter(&self) -> impl Iterator<Item = &u64> {
         self.stack.iter()
     }
 
-    pub fn push(&mut self, hash: u64) {
+    pub fn push(&mut self, hash: u64, mirrored: u64) {
         self.stack.push(hash);
+        self.mirrored.push(mirrored);
     }
 
     pub fn pop(&mut self) {
         self.stack.pop();
+        self.mirrored.pop();
+    }
+
+    /// Hashes of the mirrored game
+    pub fn mirror(&self) -> MoveStack {
+        MoveStack {
+            stack: self.mirrored.clone(),
+            mirrored: self.stack.clone(),
+        }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
 pub struct SquareMask {
     pub moves: u8,
     pub from: u8,
     pub to: u8,
-    pub path: u64,
+    pub path: u128,
+}
+
+impl SquareMask {
+    pub fn mirror(&self, variant: Variant) -> SquareMask {
+        let mirror = |sq: u8| {
+            if sq == u8::MAX {
+                sq
+            } else {
+                variant.mirror(sq)
+            }
+        };
+
+        SquareMask {
+            moves: self.moves,
+            from: mirror(self.from),
+            to: mirror(self.to),
+            path: variant.mirror_bb(self.path),
+        }
+    }
 }
 
 impl Default for SquareMask {
@@ -124,3 +167,25 @@ impl Default for SquareMask {
         }
     }
 }
+
+/// Continuous chase of one side, where the chased piece evades every time
+#[derive(Clone, Copy, PartialEq, Eq, Default, Encode, Decode)]
+pub struct Chase {
+    /// Plies since the first chasing move or zero if there is no chase
+    pub plies: u16,
+    /// Square of the chased piece before it evades
+    pub square: u8,
+}
+
+impl Chase {
+    pub fn mirror(&self, variant: Variant) -> Chase {
+        if self.plies == 0 {
+            return *self;
+        }
+
+        Chase {
+            plies: self.plies,
+            square: variant.mirror(self.square),
+        }
+    }
+}
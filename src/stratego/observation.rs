@@ -0,0 +1,104 @@
+use super::{
+    Beliefs, Event, EventKind, InformationSet, Move, MoveList, Piece, Position, StrategoState,
+};
+
+/// View of a game for one side, which only holds public and own information
+///
+/// Hidden pieces of the opponent are unknown on the board and the hashes of all positions
+/// are computed from the visible pieces, so that nothing can be derived from them. An
+/// observation is created by `StrategoState::observe` and follows the game with `update`.
+#[derive(Clone)]
+pub struct Observation {
+    side: usize,
+    state: StrategoState,
+}
+
+impl std::fmt::Display for Observation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.state)
+    }
+}
+
+impl Observation {
+    pub(super) fn new(side: usize, state: StrategoState) -> Self {
+        Self { side, state }
+    }
+
+    /// Catches up with `game`, of which this is an earlier observation, by making the moves
+    /// since as they are observed
+    ///
+    /// The ranks of both pieces of a capture are taken from the public events of the game.
+    pub fn update(&mut self, game: &StrategoState) {
+        debug_assert!(self.state.ply() <= game.ply());
+
+        for ply in self.state.ply()..game.ply() {
+            let mov = game.history[ply].mov;
+
+            let first = game.events.partition_point(|event| event.ply < ply);
+            let defender = game.events[first..]
+                .iter()
+                .take_while(|event| event.ply == ply)
+                .find_map(|event| match event.kind {
+                    EventKind::Combat { defender, .. } => Some(defender),
+                    _ => None,
+                })
+                .unwrap_or(Piece::UNKNOWN);
+
+            self.state.make_observed(mov, defender);
+        }
+
+        // End of the game is public, even if it follows from hidden pieces
+        if let Some(result) = game.result() {
+            self.state.board.end(result.state, result.termination);
+        }
+    }
+
+    /// Side, which observes the game
+    pub fn side(&self) -> usize {
+        self.side
+    }
+
+    /// Game as seen by the observing side, whose hidden pieces of the opponent are unknown
+    pub fn state(&self) -> &StrategoState {
+        &self.state
+    }
+
+    pub fn board(&self) -> Position {
+        self.state.board()
+    }
+
+    pub fn information(&self) -> InformationSet {
+        self.state.information()
+    }
+
+    pub fn gen(&self) -> MoveList {
+        self.state.gen()
+    }
+
+    pub fn stm(&self) -> bool {
+        self.state.stm()
+    }
+
+    pub fn ply(&self) -> usize {
+        self.state.ply()
+    }
+
+    pub fn history(&self) -> Vec<Move> {
+        self.state.history()
+    }
+
+    /// Public events of the game in the order they occurred
+    pub fn events(&self) -> &[Event] {
+        self.state.events()
+    }
+
+    /// Probabilities of the ranks of the opponent's hidden pieces
+    pub fn beliefs(&self) -> Beliefs {
+        self.state.beliefs(self.side)
+    }
+
+    /// Random assignment of the unknown pieces, which is consistent with the observation
+    pub fn determination(&self) -> StrategoState {
+        self.state.determination()
+    }
+}
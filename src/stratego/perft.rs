@@ -0,0 +1,202 @@
+use super::{error::PerftError, Move, StrategoState};
+
+/// Position with a known number of leaf nodes at `depth`
+pub struct PerftCase {
+    pub name: &'static str,
+    pub position: &'static str,
+    /// Moves played from the position before counting, which set up the repetition rules
+    pub moves: &'static str,
+    pub depth: usize,
+    pub nodes: usize,
+}
+
+impl PerftCase {
+    /// Position after playing `moves`
+    pub fn state(&self) -> StrategoState {
+        let mut pos = StrategoState::from(self.position);
+
+        for mov in self.moves.split_ascii_whitespace() {
+            let mov = pos.parse_move(mov).unwrap();
+            pos.make(mov);
+        }
+
+        pos
+    }
+}
+
+/// Reference positions, which cover both variants and the repetition rules
+pub const PERFT_SUITE: &[PerftCase] = &[
+    PerftCase {
+        name: "quick battle start",
+        position: "d2f4/bbg4c/1m3dsc/8/8/BD3M1G/F5SD/1BCC4 r",
+        moves: "",
+        depth: 5,
+        nodes: 5_771_408,
+    },
+    PerftCase {
+        // Chases are short enough to repeat positions, e.g. after d1d6 c7c6 d6d7 c6c7 d7d6
+        // the more-squares rule forbids c7c6, which removes 16 of the 118,373,727 nodes
+        name: "quick battle start deep",
+        position: "d2f4/bbg4c/1m3dsc/8/8/BD3M1G/F5SD/1BCC4 r",
+        moves: "",
+        depth: 6,
+        nodes: 118_373_711,
+    },
+    PerftCase {
+        name: "quick battle middlegame",
+        position: "d2f4/bb5c/1m1g1dsc/8/1M6/BD5G/F5SD/1BCC4 b",
+        moves: "",
+        depth: 4,
+        nodes: 214_388,
+    },
+    PerftCase {
+        name: "classic start",
+        position: "cljoafobbe/jcgjsacccb/leddadelem/lccbadbcdb/10/10/CBDLEDOLCD/BFJDBELOJA/EGCABDCSME/JBACBCCACL r",
+        moves: "",
+        depth: 5,
+        nodes: 84_217,
+    },
+    PerftCase {
+        name: "two-squares rule",
+        position: "m6f/8/8/8/8/8/8/F6M r",
+        moves: "h1h2 a8a7 h2h1 a7a8 h1h2 a8a7 h2h1 a7a8 h1h2 a8a7",
+        depth: 6,
+        nodes: 576,
+    },
+    PerftCase {
+        name: "two-squares rule with scouts",
+        position: "c6f/8/8/8/8/8/8/F6C r",
+        moves: "h1h4 a8a5 h4h1 a5a8 h1h4 a8a5",
+        depth: 4,
+        nodes: 6_521,
+    },
+    PerftCase {
+        name: "more-squares rule",
+        position: "3g3f/3M4/8/8/8/8/8/F7 b",
+        moves: "d8e8 d7e7 e8f8 e7f7 f8e8",
+        depth: 6,
+        nodes: 917,
+    },
+    PerftCase {
+        name: "classic chase",
+        position: "9f/10/10/10/4g5/10/4M5/10/10/F9 r",
+        moves: "e4e5 e6f6 e5f5 f6e6 f5e5 e6f6",
+        depth: 6,
+        nodes: 616,
+    },
+];
+
+/// Number of leaf nodes of the move tree with the given depth
+pub fn perft(pos: &mut StrategoState, depth: usize) -> usize {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut nodes = 0;
+
+    for mov in pos.gen().iter() {
+        pos.make(mov);
+        nodes += perft(pos, depth - 1);
+        pos.unmake();
+    }
+
+    nodes
+}
+
+/// Number of leaf nodes below each legal move
+pub fn divide(pos: &mut StrategoState, depth: usize) -> Vec<(Move, usize)> {
+    let mut nodes = Vec::new();
+
+    for mov in pos.gen().iter() {
+        pos.make(mov);
+        nodes.push((mov, perft(pos, depth.saturating_sub(1))));
+        pos.unmake();
+    }
+
+    nodes
+}
+
+/// Like `perft`, but verifies the incremental hash and that `unmake` restores the state
+pub fn perft_checked(pos: &mut StrategoState, depth: usize) -> Result<usize, PerftError> {
+    checked(pos, depth, &mut Vec::new())
+}
+
+fn checked(
+    pos: &mut StrategoState,
+    depth: usize,
+    line: &mut Vec<Move>,
+) -> Result<usize, PerftError> {
+    if depth == 0 {
+        return Ok(1);
+    }
+
+    let board = pos.board();
+    let info = pos.information();
+    let line_str = |line: &[Move]| {
+        line.iter()
+            .map(|mov| mov.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let mut nodes = 0;
+
+    for mov in pos.gen().iter() {
+        pos.make(mov);
+        line.push(mov);
+
+        let next = pos.board();
+        let hashes = [
+            (next.hash(), next.zobrist()),
+            (next.mirrored(), next.mirror().zobrist()),
+        ];
+        if let Some(&(hash, expected)) = hashes.iter().find(|(hash, expected)| hash != expected) {
+            return Err(PerftError::Hash {
+                line: line_str(line),
+                hash,
+                expected,
+            });
+        }
+
+        nodes += checked(pos, depth - 1, line)?;
+        pos.unmake();
+
+        if pos.board() != board || pos.information() != info {
+            return Err(PerftError::Unmake {
+                line: line_str(line),
+            });
+        }
+
+        line.pop();
+    }
+
+    Ok(nodes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Cases with more nodes only run with `cargo test --release -- --ignored`
+    const NODES: usize = 1_000_000;
+
+    fn check(cases: impl Iterator<Item = &'static PerftCase>) {
+        for case in cases {
+            let nodes = perft_checked(&mut case.state(), case.depth)
+                .unwrap_or_else(|err| panic!("{}: {err}", case.name));
+
+            assert_eq!(nodes, case.nodes, "{}", case.name);
+        }
+    }
+
+    #[test]
+    fn suite() {
+        check(PERFT_SUITE.iter().filter(|case| case.nodes <= NODES));
+    }
+
+    #[test]
+    #[ignore = "runs for minutes"]
+    fn suite_deep() {
+        check(PERFT_SUITE.iter().filter(|case| case.nodes > NODES));
+    }
+}
@@ -1,174 +1,375 @@
 use super::{
     attacks,
-    moves::{Move, MoveList, MoveStack, SquareMask},
-    util::Flag,
-    GameState,
+    error::ParseError,
+    moves::{Chase, Move, MoveList, MoveStack, SquareMask},
+    util::{compact, file, rank, square, Flag, WIDTH},
+    Army, GameResult, GameState, Rules, Termination, Variant,
 };
 use crate::{
     bitboard_loop,
     stratego::util::{Piece, Zobrist},
 };
+use bincode::{Decode, Encode};
 use std::cmp::Ordering;
 
 /// Represents board from pov of one player
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Encode, Decode)]
 pub struct Position {
-    bb: [u64; 10],
+    bb: [u128; 15],
+    variant: Variant,
+    army: Army,
+    rules: Rules,
     stm: bool,
-    state: GameState,
+    result: Option<GameResult>,
     hash: u64,
+    /// Hash of the position mirrored between left and right
+    mirrored: u64,
     half: u16,
     attacker: u8,
     last: [SquareMask; 2],
-    attacks: u64,
-    evading: [bool; 2],
+    attacks: u128,
+    chase: [Chase; 2],
+}
+
+/// Irreversible state of a move, which is required to unmake it
+#[derive(Clone, Copy, Encode, Decode)]
+pub struct PositionUndo {
+    result: Option<GameResult>,
+    half: u16,
+    attacker: u8,
+    last: SquareMask,
+    attacks: u128,
+    chase: [Chase; 2],
+    captured: u8,
+    unknown: bool,
+}
+
+impl PositionUndo {
+    /// Undo of the mirrored move on the board of `variant`
+    pub fn mirror(&self, variant: Variant) -> PositionUndo {
+        PositionUndo {
+            last: self.last.mirror(variant),
+            attacks: variant.mirror_bb(self.attacks),
+            chase: self.chase.map(|chase| chase.mirror(variant)),
+            ..*self
+        }
+    }
 }
 
 impl std::fmt::Display for Position {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        const DELIMITER: &str = concat!("+---+---+---+---+---+---+---+---+", '\n');
+        let (files, ranks) = (self.variant.files(), self.variant.ranks());
+        let delimiter = format!("{}+\n", "+---".repeat(files));
 
         let mut pos = self.chars();
 
-        let mut lakes = Position::LAKES;
+        let mut lakes = self.variant.lakes();
         bitboard_loop!(lakes, sq, pos[sq as usize] = '~');
 
-        let mut pos_str = String::from(DELIMITER);
-        let mut notation = String::new();
+        let mut pos_str = delimiter.clone();
+
+        for rank in (0..ranks).rev() {
+            let start = rank * WIDTH as usize;
 
-        for rank in (0..8).rev() {
-            let start = rank * 8;
+            let rank_board: String = pos[start..(start + files)]
+                .iter()
+                .map(|c| format!("| {c} "))
+                .collect();
 
-            let mut rank_notation = String::new();
-            let mut rank_board = String::new();
-            let mut spaces = 0;
+            pos_str.push_str(&format!("{}| {}\n{}", rank_board, rank + 1, delimiter));
+        }
 
-            for &c in &pos[start..(start + 8)] {
-                rank_board.push_str(&format!("| {c} "));
+        let files = (b'a'..).take(files).map(|c| format!("  {} ", c as char));
+        pos_str.push_str(files.collect::<String>().trim_end());
+        write!(f, "{pos_str}\n\nNotation: {}", self.notation())
+    }
+}
 
-                if c == ' ' || c == '~' {
-                    spaces += 1;
+impl std::str::FromStr for Position {
+    type Err = ParseError;
 
-                    continue;
+    fn from_str(notation: &str) -> Result<Self, Self::Err> {
+        Position::parse(notation, None)
+    }
+}
+
+impl TryFrom<&str> for Position {
+    type Error = ParseError;
+
+    fn try_from(notation: &str) -> Result<Self, Self::Error> {
+        notation.parse()
+    }
+}
+
+impl Position {
+    pub const SYMBOLS: [char; 26] = [
+        'F', 'S', 'C', 'D', 'E', 'L', 'A', 'J', 'O', 'G', 'M', 'X', 'B', 'f', 's', 'c', 'd', 'e',
+        'l', 'a', 'j', 'o', 'g', 'm', 'x', 'b',
+    ];
+
+    pub const fn symbol(side: usize, piece: usize) -> char {
+        Position::SYMBOLS[side * 13 + piece - 2]
+    }
+
+    pub fn from(notation: &str) -> Self {
+        notation.parse().unwrap()
+    }
+
+    /// Notation of the board and the side to move, from which the position can be parsed
+    pub fn notation(&self) -> String {
+        let (files, ranks) = (self.variant.files(), self.variant.ranks());
+        let pos = self.chars();
+
+        let mut notation = (0..ranks)
+            .rev()
+            .map(|rank| {
+                let start = rank * WIDTH as usize;
+
+                let mut rank_notation = String::new();
+                let mut spaces = 0;
+
+                for &c in &pos[start..(start + files)] {
+                    if c == ' ' {
+                        spaces += 1;
+
+                        continue;
+                    }
+
+                    if spaces > 0 {
+                        rank_notation.push_str(&spaces.to_string());
+                        spaces = 0;
+                    }
+
+                    rank_notation.push(c);
                 }
 
                 if spaces > 0 {
                     rank_notation.push_str(&spaces.to_string());
-                    spaces = 0;
                 }
 
-                rank_notation.push(c);
-            }
-
-            if spaces > 0 {
-                rank_notation.push_str(&spaces.to_string());
-            }
-
-            notation.push_str(&format!("{}/", rank_notation));
-            pos_str.push_str(&format!("{}| {}\n{}", rank_board, rank + 1, DELIMITER));
-        }
-
-        // Remove last backslash
-        notation.pop();
+                rank_notation
+            })
+            .collect::<Vec<_>>()
+            .join("/");
 
         notation.push(' ');
         notation.push(if self.stm { 'b' } else { 'r' });
 
-        pos_str.push_str("  a   b   c   d   e   f   g   h");
-        write!(f, "{pos_str}\n\nNotation: {}", notation)
+        notation
     }
-}
 
-impl Position {
-    pub const SYMBOLS: [char; 16] = [
-        'F', 'S', 'C', 'D', 'G', 'M', 'X', 'B', 'f', 's', 'c', 'd', 'g', 'm', 'x', 'b',
-    ];
-    pub const LAKES: u64 = 0x2424000000;
+    /// Parses a position, whose pieces belong to `army` instead of the default army
+    pub fn with_army(notation: &str, army: Army) -> Result<Self, ParseError> {
+        Position::parse(notation, Some(army))
+    }
 
-    pub fn from(notation: &str) -> Self {
-        let fields = notation.split(' ').collect::<Vec<&str>>();
+    fn parse(notation: &str, army: Option<Army>) -> Result<Self, ParseError> {
+        let mut fields = notation.split_ascii_whitespace();
+        let board = fields.next().unwrap_or_default();
+        let ranks = board.split('/').count();
+
+        let variant = Variant::from_ranks(ranks)
+            .filter(|variant| army.is_none_or(|army| army.variant() == *variant))
+            .ok_or(ParseError::InvalidRanks(ranks))?;
+        let files = variant.files();
 
         let mut pos = Self {
-            bb: [0u64; 10],
+            bb: [0u128; 15],
+            variant,
+            army: army.unwrap_or(variant.army()),
+            rules: Rules::default(),
             stm: false,
-            state: GameState::default(),
+            result: None,
             hash: 0,
+            mirrored: 0,
             half: 0,
             attacker: 0,
             last: [SquareMask::default(); 2],
             attacks: 0,
-            evading: [false; 2],
+            chase: [Chase::default(); 2],
         };
 
-        let (mut file, mut rank) = (0, 7);
-        for c in fields[0].chars() {
-            match c {
-                c if c.is_numeric() => file += c as u32 - '0' as u32,
-                '/' => (file, rank) = (0, rank - 1),
-                _ => {
-                    let side = c.is_ascii_lowercase() as usize;
-                    let c = c.to_ascii_uppercase();
-                    let piece = Position::SYMBOLS
-                        .iter()
-                        .position(|&symbol| symbol == c)
-                        .unwrap()
-                        .wrapping_add(2);
-
-                    pos.toggle(side, piece, (rank * 8 + file) as u8);
-
-                    file += 1;
+        for (i, rank_notation) in board.split('/').enumerate() {
+            let rank = ranks - 1 - i;
+
+            let mut file = 0;
+            let mut empty = 0;
+            for c in rank_notation.chars() {
+                // Empty squares can span multiple digits, e.g. `10`
+                if let Some(digit) = c.to_digit(10) {
+                    empty = empty * 10 + digit as usize;
+
+                    // Rejected before long runs of digits overflow
+                    if file + empty > files {
+                        return Err(ParseError::InvalidFiles {
+                            rank,
+                            files: file + empty,
+                        });
+                    }
+
+                    continue;
                 }
-            };
+
+                file += empty;
+                empty = 0;
+
+                if file >= files {
+                    return Err(ParseError::InvalidFiles {
+                        rank,
+                        files: file + 1,
+                    });
+                }
+
+                let side = c.is_ascii_lowercase() as usize;
+                let piece = (Piece::FLAG..=Piece::BOMB)
+                    .find(|&piece| Position::symbol(side, piece) == c)
+                    .ok_or(ParseError::InvalidSymbol {
+                        rank,
+                        file,
+                        symbol: c,
+                    })?;
+
+                let sq = square(file as u8, rank as u8);
+                if (variant.lakes() & (1u128 << sq)) != 0 {
+                    return Err(ParseError::Lake {
+                        rank,
+                        file,
+                        symbol: c,
+                    });
+                }
+
+                pos.toggle(side, piece, sq);
+
+                file += 1;
+            }
+
+            file += empty;
+            if file != files {
+                return Err(ParseError::InvalidFiles { rank, files: file });
+            }
         }
 
-        pos.stm = fields[1] != "r";
+        pos.stm = match fields.next() {
+            Some("r") => false,
+            Some("b") => true,
+            Some(side) => return Err(ParseError::InvalidSide(side.to_string())),
+            None => return Err(ParseError::MissingSide),
+        };
 
-        pos
+        pos.validate()?;
+
+        Ok(pos)
     }
 
     pub fn stm(&self) -> bool {
         self.stm
     }
 
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
+    pub fn army(&self) -> Army {
+        self.army
+    }
+
+    pub fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
     pub fn game_state(&self) -> GameState {
-        self.state
+        self.result
+            .map_or(GameState::Ongoing, |result| result.state)
+    }
+
+    /// Result of the game, if it has ended
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
     }
 
-    pub fn set_game_state(&mut self, state: GameState) {
-        self.state = state;
+    /// Ends the game with `state` from the pov of the side to move
+    pub fn end(&mut self, state: GameState, termination: Termination) {
+        self.result = Some(GameResult { state, termination });
     }
 
     pub fn game_over(&self) -> bool {
-        self.state != GameState::Ongoing
+        self.result.is_some()
     }
 
     pub fn hash(&self) -> u64 {
         self.hash
     }
 
+    /// Hash of the mirrored position, which is kept up to date like `hash`
+    pub fn mirrored(&self) -> u64 {
+        self.mirrored
+    }
+
+    /// Hash computed from scratch, which must always equal the incremental `hash`
+    pub fn zobrist(&self) -> u64 {
+        let mut hash = 0;
+
+        for side in 0..2 {
+            for piece in Piece::FLAG..=Piece::BOMB {
+                let mut bb = self.bb[side] & self.bb[piece];
+                bitboard_loop!(bb, sq, hash ^= Zobrist::get(side, sq as usize, piece));
+            }
+        }
+
+        hash
+    }
+
     pub fn half(&self) -> usize {
         self.half as usize
     }
 
-    pub fn get(&self, index: usize) -> u64 {
+    pub fn get(&self, index: usize) -> u128 {
         self.bb[index]
     }
 
-    pub fn bb(&self) -> [u64; 10] {
+    pub fn bb(&self) -> [u128; 15] {
         self.bb
     }
 
+    /// Bitboard in the 8x8 layout, which is only meaningful for Quick Battle
+    pub fn compact(&self, index: usize) -> u64 {
+        compact(self.bb[index])
+    }
+
+    /// Position mirrored between the left and right side of the board
+    ///
+    /// The lakes are symmetric, so the mirrored position is legal and the rules apply to it
+    /// in the same way, including the squares of the two-squares rule and chases.
+    pub fn mirror(&self) -> Position {
+        let variant = self.variant;
+
+        Position {
+            bb: self.bb.map(|bb| variant.mirror_bb(bb)),
+            hash: self.mirrored,
+            mirrored: self.hash,
+            last: self.last.map(|last| last.mirror(variant)),
+            attacks: variant.mirror_bb(self.attacks),
+            chase: self.chase.map(|chase| chase.mirror(variant)),
+            ..*self
+        }
+    }
+
     pub fn toggle(&mut self, stm: usize, piece: usize, sq: u8) {
-        let bb = 1u64 << sq;
+        let bb = 1u128 << sq;
 
         self.hash ^= Zobrist::get(stm, sq as usize, piece);
+        self.mirrored ^= Zobrist::get(stm, self.variant.mirror(sq) as usize, piece);
 
         self.bb[stm] ^= bb;
         self.bb[piece] ^= bb;
     }
 
     pub fn piece(&self, sq: u8) -> usize {
-        let bb = 1u64 << sq;
+        let bb = 1u128 << sq;
 
         self.bb
             .iter()
@@ -178,15 +379,31 @@ impl Position {
             .wrapping_add(2)
     }
 
-    pub fn make(&mut self, mov: &Move) {
+    /// Maximum number of consecutive moves of a piece between the same two squares
+    pub const TWO_SQUARES: u8 = 5;
+
+    pub fn make(&mut self, mov: &Move) -> PositionUndo {
         let stm = usize::from(self.stm);
         let piece = mov.piece as usize;
 
+        let mut undo = PositionUndo {
+            result: self.result,
+            half: self.half,
+            attacker: self.attacker,
+            last: self.last[stm],
+            attacks: self.attacks,
+            chase: self.chase,
+            captured: 0,
+            unknown: ((1u128 << mov.from) & self.bb[Piece::UNKNOWN]) != 0,
+        };
+
+        self.update_chase(stm, mov);
+
         // Increase two-squares counter if moving back to previous square or
         // traversing along path (piece must be on path already)
         self.last[stm].moves = if (self.last[stm].from == mov.to && self.last[stm].to == mov.from)
-            || ((self.last[stm].path & (1u64 << mov.to)) != 0
-                && (self.last[stm].path & (1u64 << mov.from)) != 0)
+            || ((self.last[stm].path & (1u128 << mov.to)) != 0
+                && (self.last[stm].path & (1u128 << mov.from)) != 0)
         {
             self.last[stm].moves + 1
         } else {
@@ -205,12 +422,10 @@ impl Position {
         self.last[stm].from = mov.from;
         self.last[stm].to = mov.to;
         // Store possible attacks in next turn to check if opponent is evading
-        self.attacks = attacks::orthogonal(mov.to as usize);
-        // Check if next move can't be repetitive
-        self.evading[stm] = (mov.flag & Flag::EVADING) != 0;
+        self.attacks = attacks::adjacent(mov.to as usize) & self.variant.squares();
 
         // Remove piece from old square
-        if ((1u64 << mov.from) & self.bb[Piece::UNKNOWN]) != 0 {
+        if undo.unknown {
             self.toggle(stm, Piece::UNKNOWN, mov.from);
         } else {
             self.toggle(stm, piece, mov.from);
@@ -222,7 +437,7 @@ impl Position {
             self.half += 1;
             self.attacker = 0;
 
-            return;
+            return undo;
         }
 
         // Captures can only be done by piece with known rank
@@ -233,17 +448,9 @@ impl Position {
         self.attacker = mov.piece;
 
         let other = self.piece(mov.to);
+        undo.captured = other as u8;
 
-        let ordering = if (piece == Piece::SPY && other == Piece::MARSHAL)
-            || (piece == Piece::MINER && other == Piece::BOMB)
-        {
-            // Spy can capture general or miner can defuse bomb
-            Ordering::Greater
-        } else {
-            piece.cmp(&other)
-        };
-
-        match ordering {
+        match self.rules.combat(piece, other) {
             // Do nothing, because `attacker` is already removed
             Ordering::Less => {}
             // Delete only other, because `attacker` is already removed
@@ -259,42 +466,74 @@ impl Position {
 
         // Current player has captured the flag
         if other == Piece::FLAG {
-            self.state = GameState::Loss;
+            self.end(GameState::Loss, Termination::FlagCaptured);
         }
         // If all bitboards except the immovable pieces are empty the game is drawn
         else if ((self.bb[0] | self.bb[1]) ^ immovable) == 0 {
-            self.state = GameState::Draw;
+            self.end(GameState::Draw, Termination::BothImmovable);
         }
         // If the current side has no pieces the other wins
         else if (self.bb[stm] & !immovable) == 0 {
-            self.state = GameState::Win;
+            self.end(GameState::Win, Termination::NoMovablePieces);
         }
         // If other side has no pieces the current side wins
         else if (self.bb[stm ^ 1] & !immovable) == 0 {
-            self.state = GameState::Loss;
+            self.end(GameState::Loss, Termination::NoMovablePieces);
+        }
+
+        undo
+    }
+
+    pub fn unmake(&mut self, mov: &Move, undo: &PositionUndo) {
+        self.stm ^= true;
+
+        let stm = usize::from(self.stm);
+        let piece = mov.piece as usize;
+        let to_bb = 1u128 << mov.to;
+
+        if (mov.flag & Flag::CAPTURE) == 0 {
+            self.toggle(stm, piece, mov.to);
+        } else {
+            let other = undo.captured as usize;
+
+            // Outcome of the capture can be derived from the remaining piece
+            if (self.bb[stm] & to_bb) != 0 {
+                self.toggle(stm, piece, mov.to);
+                self.toggle(stm ^ 1, other, mov.to);
+            } else if (self.bb[stm ^ 1] & to_bb) == 0 {
+                self.toggle(stm ^ 1, other, mov.to);
+            }
+        }
+
+        if undo.unknown {
+            self.toggle(stm, Piece::UNKNOWN, mov.from);
+        } else {
+            self.toggle(stm, piece, mov.from);
         }
+
+        self.result = undo.result;
+        self.half = undo.half;
+        self.attacker = undo.attacker;
+        self.last[stm] = undo.last;
+        self.attacks = undo.attacks;
+        self.chase = undo.chase;
     }
 
     pub fn gen(&self, stack: &MoveStack) -> MoveList {
         let mut moves = MoveList::default();
         // If opponent has won the game in the last turn, no moves are generated
-        if self.state != GameState::Ongoing {
+        if self.game_over() {
             return moves;
         }
 
         let stm = usize::from(self.stm);
-        let attacks = self.attacks(stm ^ 1);
-        let occ = self.bb[0] | self.bb[1] | Position::LAKES;
+        let occ = self.bb[0] | self.bb[1] | self.variant.blocked();
 
-        let from_mask = if self.last[stm].from != u8::MAX {
-            1u64 << self.last[stm].from
-        } else {
-            0
-        };
+        let square_mask = self.two_squares(stm);
 
-        // Remove path from attacks on third repetition
-        let square_mask = if self.last[stm].moves == 2 {
-            self.last[stm].path | from_mask
+        // Only moves continuing a chase are restricted by the more-squares rule
+        let threats = if self.chase[stm].plies > 0 {
+            self.threats(stm)
         } else {
             0
         };
@@ -303,39 +542,17 @@ impl Position {
             let mut piece_mask = self.bb[piece] & self.bb[stm];
 
             bitboard_loop!(piece_mask, from, {
-                let from_bb = 1u64 << from;
-
-                let mut attack_mask = match piece {
-                    Piece::SCOUT => attacks::ranged(from as usize, occ),
-                    _ => attacks::orthogonal(from as usize),
-                };
-
-                // Moving back to previous square/path is forbidden on third time
-                if self.last[stm].to == from {
-                    attack_mask ^= square_mask;
-                }
-
-                // `occ` already includes `LAKES`
-                let mut quiets = attack_mask & !occ;
+                let (mut quiets, mut captures) =
+                    self.targets(stack, piece, from, occ, square_mask, threats);
 
                 // If opponent's piece is chasing then all quiet moves are evading
-                let move_flag = if (self.attacks & from_bb) != 0 {
+                let move_flag = if (self.attacks & (1u128 << from)) != 0 {
                     Flag::EVADING
                 } else {
                     Flag::QUIET
                 };
 
-                // Piece can't move back to old position when chasing except the previous position
-                let repetitions = quiets & attacks & !from_mask;
-                if self.evading[stm ^ 1] && repetitions != 0 {
-                    quiets ^= self.repetition(stack, stm, piece, from, repetitions);
-                }
-
                 bitboard_loop!(quiets, to, moves.push(from, to, move_flag, piece as u8));
-
-                // `ranged` and `orthogonal` don't subtract `LAKES` implicitly
-                let mut captures = attack_mask & self.bb[stm ^ 1] & !Position::LAKES;
-
                 bitboard_loop!(
                     captures,
                     to,
@@ -347,47 +564,253 @@ impl Position {
         moves
     }
 
-    fn attacks(&self, stm: usize) -> u64 {
-        let mut bb = self.bb[stm];
-        let mut attacks = 0;
+    /// Whether the side to move has a legal move, which is cheaper than generating all moves
+    ///
+    /// Pieces of unknown rank might be movable, so a side with such pieces is assumed to
+    /// have a legal move, although `gen` doesn't move them. Perfect information searches
+    /// determinize such positions and simulations count a side without moves as lost.
+    pub fn has_moves(&self, stack: &MoveStack) -> bool {
+        if self.game_over() {
+            return false;
+        }
 
-        bitboard_loop!(bb, sq, attacks |= attacks::orthogonal(sq as usize));
+        let stm = usize::from(self.stm);
+        if (self.bb[Piece::UNKNOWN] & self.bb[stm]) != 0 {
+            return true;
+        }
 
-        attacks
+        let occ = self.bb[0] | self.bb[1] | self.variant.blocked();
+        let square_mask = self.two_squares(stm);
+        let threats = if self.chase[stm].plies > 0 {
+            self.threats(stm)
+        } else {
+            0
+        };
+
+        (Piece::SPY..=Piece::MARSHAL).any(|piece| {
+            let mut piece_mask = self.bb[piece] & self.bb[stm];
+
+            bitboard_loop!(piece_mask, from, {
+                let (quiets, captures) =
+                    self.targets(stack, piece, from, occ, square_mask, threats);
+
+                if (quiets | captures) != 0 {
+                    return true;
+                }
+            });
+
+            false
+        })
     }
 
-    fn repetition(&self, stack: &MoveStack, stm: usize, piece: usize, from: u8, bb: u64) -> u64 {
+    /// Quiet and capture targets of `piece` of the side to move on `from`
+    fn targets(
+        &self,
+        stack: &MoveStack,
+        piece: usize,
+        from: u8,
+        occ: u128,
+        square_mask: u128,
+        threats: u128,
+    ) -> (u128, u128) {
+        let stm = usize::from(self.stm);
+        let mut attack_mask = match piece {
+            Piece::SCOUT => attacks::sliding(from as usize, occ),
+            _ => attacks::adjacent(from as usize),
+        };
+
+        // Moving back to previous square/path is forbidden after the limit
+        if self.last[stm].to == from {
+            attack_mask &= !square_mask;
+        }
+
+        // `occ` already includes lakes and squares outside the board
+        let mut quiets = attack_mask & !occ;
+
+        // Chasing side can't repeat a position, which occurred during the chase
+        let repetitions = quiets & threats;
+        if repetitions != 0 {
+            quiets ^= self.repetition(stack, stm, piece, from, repetitions);
+        }
+
+        // Opponent's pieces can't be on lakes or outside the board
+        let mut captures = attack_mask & self.bb[stm ^ 1];
+
+        // Scout can only capture adjacent pieces, if it can't strike after moving
+        if piece == Piece::SCOUT && !self.rules.scout_strikes {
+            captures &= attacks::adjacent(from as usize);
+        }
+
+        (quiets, captures)
+    }
+
+    /// Squares the last moved piece of `stm` can't move to because of the two-squares rule
+    pub fn two_squares(&self, stm: usize) -> u128 {
+        let last = self.last[stm];
+
+        // Remove path from attacks if the piece has reached the two-squares limit
+        if last.from == u8::MAX || last.moves + 1 < Position::TWO_SQUARES {
+            return 0;
+        }
+
+        last.path | (1u128 << last.from)
+    }
+
+    /// Square of the last moved piece of `stm`
+    pub fn last(&self, stm: usize) -> Option<u8> {
+        Some(self.last[stm].to).filter(|&sq| sq != u8::MAX)
+    }
+
+    /// Squares of the move, which led to the position
+    pub fn last_move(&self) -> Option<(u8, u8)> {
+        let last = self.last[usize::from(!self.stm)];
+
+        Some((last.from, last.to)).filter(|&(from, _)| from != u8::MAX)
+    }
+
+    /// Piece, which attacked with the move leading to the position
+    pub fn attacker(&self) -> Option<usize> {
+        Some(self.attacker as usize).filter(|&piece| piece != 0)
+    }
+
+    /// Squares threatening the piece, which the opponent of `stm` moved last
+    fn threats(&self, stm: usize) -> u128 {
+        let to = self.last[stm ^ 1].to;
+
+        if to == u8::MAX || (self.bb[stm ^ 1] & (1u128 << to)) == 0 {
+            return 0;
+        }
+
+        attacks::adjacent(to as usize)
+    }
+
+    /// Continues or ends the chases of both sides according to the ISF rules
+    fn update_chase(&mut self, stm: usize, mov: &Move) {
+        let capture = (mov.flag & Flag::CAPTURE) != 0;
+
+        // Chase of the opponent only continues if the chased piece evades
+        let chase = &mut self.chase[stm ^ 1];
+        if chase.plies > 0 {
+            *chase = if !capture && mov.from == chase.square {
+                Chase {
+                    plies: chase.plies + 1,
+                    square: mov.to,
+                }
+            } else {
+                Chase::default()
+            };
+        }
+
+        // Moving next to the piece the opponent just moved starts or continues a chase
+        self.chase[stm] = if !capture && (self.threats(stm) & (1u128 << mov.to)) != 0 {
+            Chase {
+                plies: self.chase[stm].plies + 1,
+                square: self.last[stm ^ 1].to,
+            }
+        } else {
+            Chase::default()
+        };
+    }
+
+    /// Squares the piece on `from` can't move to because of the more-squares rule,
+    /// i.e. the chasing side can't repeat a position of the current chase
+    fn repetition(&self, stack: &MoveStack, stm: usize, piece: usize, from: u8, bb: u128) -> u128 {
         let hash = self.hash ^ Zobrist::get(stm, from as usize, piece);
+        let plies = self.chase[stm].plies as usize;
 
         let mut repetitions = 0;
         let mut bb = bb;
         bitboard_loop!(bb, sq, {
-            if stack.repetition(self.half(), hash ^ Zobrist::get(stm, sq as usize, piece)) {
-                repetitions |= 1u64 << sq;
+            if stack.repeated(plies, hash ^ Zobrist::get(stm, sq as usize, piece)) {
+                repetitions |= 1u128 << sq;
             }
         });
 
         repetitions
     }
 
-    fn chars(&self) -> [char; 64] {
-        let mut pos = [' '; 64];
+    /// Checks the pieces of both sides against the army
+    fn validate(&self) -> Result<(), ParseError> {
+        let army = self.army;
+
+        for side in 0..2 {
+            let count = |piece: usize| (self.bb[side] & self.bb[piece]).count_ones() as usize;
+
+            for piece in Piece::FLAG..=Piece::BOMB {
+                if piece != Piece::UNKNOWN && count(piece) > army.count(piece) {
+                    return Err(ParseError::PieceCount {
+                        side,
+                        piece,
+                        count: count(piece),
+                        expected: army.count(piece),
+                    });
+                }
+            }
+
+            let total = self.bb[side].count_ones() as usize;
+            if total > army.len() {
+                return Err(ParseError::PieceCount {
+                    side,
+                    piece: Piece::UNKNOWN,
+                    count: total,
+                    expected: army.len(),
+                });
+            }
+
+            // Flag and bombs of an initial setup must be deployed in the start rows
+            if (Piece::FLAG..=Piece::BOMB).any(|piece| count(piece) != army.count(piece)) {
+                continue;
+            }
+
+            let immovable = self.bb[side] & (self.bb[Piece::FLAG] | self.bb[Piece::BOMB]);
+            let outside = immovable & !self.variant.start_rows(side);
+            if outside != 0 {
+                let sq = outside.trailing_zeros() as u8;
+
+                return Err(ParseError::Deployment {
+                    rank: rank(sq) as usize,
+                    file: file(sq) as usize,
+                    symbol: Position::symbol(side, self.piece(sq)),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn chars(&self) -> [char; 100] {
+        let mut pos = [' '; 100];
 
         for piece in Piece::FLAG..=Piece::BOMB {
             let mut piece_mask = self.bb[piece];
 
             bitboard_loop!(piece_mask, sq, {
-                let bb = 1 << sq as u64;
-                let offset = if (bb & self.bb[0]) != 0 { 0 } else { 8 };
+                let bb = 1u128 << sq;
+                let side = usize::from((bb & self.bb[0]) == 0);
 
                 if pos[sq as usize] != ' ' {
                     unreachable!()
                 }
 
-                pos[sq as usize] = Position::SYMBOLS[piece + offset - 2];
+                pos[sq as usize] = Position::symbol(side, piece);
             });
         }
 
         pos
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn long_digit_runs_are_invalid_files() {
+        let notation = format!("d2f4/bbg4c/1m3dsc/{}/8/BD3M1G/F5SD/1BCC4 r", "9".repeat(40));
+
+        assert!(matches!(
+            notation.parse::<Position>(),
+            Err(ParseError::InvalidFiles { rank: 4, .. })
+        ));
+    }
+}
@@ -0,0 +1,416 @@
+use super::{
+    error::RecordParseError, Army, Flag, GameState, Move, MoveLimit, Piece, Rules, StrategoState,
+    Variant,
+};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Result of a recorded game from the point of view of red
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Outcome {
+    RedWins,
+    BlueWins,
+    Draw,
+    #[default]
+    Unfinished,
+}
+
+impl std::fmt::Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let result = match self {
+            Outcome::RedWins => "1-0",
+            Outcome::BlueWins => "0-1",
+            Outcome::Draw => "1/2-1/2",
+            Outcome::Unfinished => "*",
+        };
+
+        write!(f, "{result}")
+    }
+}
+
+impl Outcome {
+    /// Outcome of the game in `pos`, whose game state is relative to the side to move
+    pub fn from(pos: &StrategoState) -> Self {
+        let stm = pos.stm() as usize;
+
+        match pos.game_state() {
+            GameState::Ongoing => Outcome::Unfinished,
+            GameState::Draw => Outcome::Draw,
+            GameState::Win => Outcome::winner(stm),
+            GameState::Loss => Outcome::winner(stm ^ 1),
+        }
+    }
+
+    pub fn parse(result: &str) -> Option<Self> {
+        match result {
+            "1-0" => Some(Outcome::RedWins),
+            "0-1" => Some(Outcome::BlueWins),
+            "1/2-1/2" => Some(Outcome::Draw),
+            "*" => Some(Outcome::Unfinished),
+            _ => None,
+        }
+    }
+
+    /// Win of `side`, where red is 0 and blue is 1
+    pub fn winner(side: usize) -> Self {
+        if side == 0 {
+            Outcome::RedWins
+        } else {
+            Outcome::BlueWins
+        }
+    }
+}
+
+/// Game with its players, deployments and moves in a PGN-like format
+///
+/// ```text
+/// [Red "pimc"]
+/// [Blue "random"]
+/// [Date "2025.03.01"]
+/// [Result "1-0"]
+/// [Termination "flag captured"]
+/// [MoveLimit "150"]
+/// [Rules "attacker-wins-ties"]
+/// [Army "FSCCDGMB"]
+/// [RedDeployment "BD3M1G/F5SD/1BCC4"]
+/// [BlueDeployment "d2f4/bbg4c/1m3dsc"]
+///
+/// 1. b3b4 c6c5
+/// 2. b4b5x3 3xh6h5
+/// 1-0
+/// ```
+///
+/// Moves use the notation of the README, i.e. a capture has the rank of the moved piece as
+/// suffix and the next move has the rank of the attacked piece as prefix. Red always moves
+/// first and both deployments are given from top to bottom like in a position. The rules
+/// and the army are only written, if they differ from the default. A limit of the total
+/// plies is written as `MoveLimit` instead of in the rules.
+#[derive(Clone)]
+pub struct GameRecord {
+    pub red: String,
+    pub blue: String,
+    pub date: String,
+    pub result: Outcome,
+    pub termination: String,
+    pub rules: Rules,
+    /// Army of both sides, which also defines the board
+    pub army: Army,
+    pub deployments: [String; 2],
+    pub moves: Vec<Move>,
+}
+
+impl std::fmt::Display for GameRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[Red \"{}\"]", self.red)?;
+        writeln!(f, "[Blue \"{}\"]", self.blue)?;
+        writeln!(f, "[Date \"{}\"]", self.date)?;
+        writeln!(f, "[Result \"{}\"]", self.result)?;
+        writeln!(f, "[Termination \"{}\"]", self.termination)?;
+        let mut rules = self.rules;
+        if let MoveLimit::Total(plies) = rules.limit {
+            writeln!(f, "[MoveLimit \"{plies}\"]")?;
+            rules.limit = MoveLimit::None;
+        }
+        if rules != Rules::default() {
+            writeln!(f, "[Rules \"{rules}\"]")?;
+        }
+        if self.army != self.army.variant().army() {
+            writeln!(f, "[Army \"{}\"]", self.army)?;
+        }
+        writeln!(f, "[RedDeployment \"{}\"]", self.deployments[0])?;
+        writeln!(f, "[BlueDeployment \"{}\"]", self.deployments[1])?;
+        writeln!(f)?;
+
+        // Ranks of attacked pieces are only known, if the moves can be replayed
+        let mut pos = self.start().ok();
+        let mut prefix = String::new();
+
+        for (ply, mov) in self.moves.iter().enumerate() {
+            if ply % 2 == 0 {
+                write!(f, "{}. ", ply / 2 + 1)?;
+            }
+
+            write!(f, "{prefix}{mov}")?;
+            if ply % 2 == 1 || ply + 1 == self.moves.len() {
+                writeln!(f)?;
+            } else {
+                write!(f, " ")?;
+            }
+
+            prefix = String::new();
+            if let Some(pos) = pos.as_mut() {
+                if (mov.flag & Flag::CAPTURE) != 0 {
+                    prefix = format!("{}x", Piece::rank(pos.board().piece(mov.to)));
+                }
+
+                pos.make(*mov);
+            }
+        }
+
+        writeln!(f, "{}", self.result)
+    }
+}
+
+impl std::str::FromStr for GameRecord {
+    type Err = RecordParseError;
+
+    fn from_str(record: &str) -> Result<Self, Self::Err> {
+        let mut headers = Vec::new();
+        let mut movetext = String::new();
+
+        for line in record
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+        {
+            if line.starts_with('[') {
+                headers.push(parse_header(line)?);
+            } else {
+                movetext.push_str(line);
+                movetext.push(' ');
+            }
+        }
+
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(header, _)| header == name)
+                .map(|(_, value)| value.clone())
+        };
+        let required =
+            |name: &'static str| header(name).ok_or(RecordParseError::MissingHeader(name));
+        let invalid = |name: &str, value: String| RecordParseError::InvalidValue {
+            name: name.to_string(),
+            value,
+        };
+
+        let result = required("Result")?;
+        let result = Outcome::parse(&result).ok_or_else(|| invalid("Result", result))?;
+
+        let mut rules = match header("Rules") {
+            Some(rules) => Rules::parse(&rules).ok_or_else(|| invalid("Rules", rules))?,
+            None => Rules::default(),
+        };
+
+        // Limit of the total plies, which must not contradict the limit of the rules
+        if let Some(limit) = header("MoveLimit") {
+            let plies = limit
+                .parse()
+                .map_err(|_| invalid("MoveLimit", limit.clone()))?;
+
+            match rules.limit {
+                MoveLimit::None => rules.limit = MoveLimit::Total(plies),
+                MoveLimit::Total(total) if total == plies => {}
+                _ => return Err(invalid("MoveLimit", limit)),
+            }
+        }
+
+        let deployments = [required("RedDeployment")?, required("BlueDeployment")?];
+        let variant = Variant::from_deployment(&deployments[0])
+            .ok_or_else(|| invalid("RedDeployment", deployments[0].clone()))?;
+
+        let army = match header("Army") {
+            Some(army) => Army::parse(variant, &army).ok_or_else(|| invalid("Army", army))?,
+            None => variant.army(),
+        };
+
+        let mut game = GameRecord {
+            red: header("Red").unwrap_or_default(),
+            blue: header("Blue").unwrap_or_default(),
+            date: header("Date").unwrap_or_default(),
+            result,
+            termination: header("Termination").unwrap_or_default(),
+            rules,
+            army,
+            deployments,
+            moves: Vec::new(),
+        };
+
+        let mut pos = game.start()?;
+        let mut revealed = None;
+
+        for token in movetext.split_ascii_whitespace() {
+            // Move numbers
+            if token
+                .strip_suffix('.')
+                .is_some_and(|number| number.parse::<usize>().is_ok())
+            {
+                continue;
+            }
+
+            if let Some(outcome) = Outcome::parse(token) {
+                if outcome != game.result {
+                    return Err(RecordParseError::ResultMismatch(token.to_string()));
+                }
+
+                break;
+            }
+
+            let ply = game.moves.len();
+
+            if let Some((prefix, _)) = token.split_once('x') {
+                let rank = Piece::from_rank(prefix);
+
+                if rank.is_some() && rank != revealed {
+                    return Err(RecordParseError::Capture {
+                        ply,
+                        rank: prefix.to_string(),
+                    });
+                }
+            }
+
+            let mov = pos
+                .parse_move(token)
+                .map_err(|error| RecordParseError::Move { ply, error })?;
+
+            revealed = ((mov.flag & Flag::CAPTURE) != 0).then(|| pos.board().piece(mov.to));
+
+            pos.make(mov);
+            game.moves.push(mov);
+        }
+
+        Ok(game)
+    }
+}
+
+impl GameRecord {
+    /// Empty record of a game played today, the deployments are given for red and blue
+    pub fn new(red: &str, blue: &str, deployments: [String; 2]) -> Self {
+        let variant = Variant::from_deployment(&deployments[0]).unwrap_or_default();
+
+        Self {
+            red: red.to_string(),
+            blue: blue.to_string(),
+            date: today(),
+            result: Outcome::Unfinished,
+            termination: String::new(),
+            rules: Rules::default(),
+            army: variant.army(),
+            deployments,
+            moves: Vec::new(),
+        }
+    }
+
+    /// Start position of the game with red to move and the rules of the game
+    pub fn start(&self) -> Result<StrategoState, RecordParseError> {
+        let notation = self
+            .army
+            .variant()
+            .start(&self.deployments[0], &self.deployments[1]);
+
+        let mut pos =
+            StrategoState::with_army(&notation, self.army).map_err(RecordParseError::Position)?;
+        pos.set_rules(self.rules);
+
+        Ok(pos)
+    }
+
+    /// Position after the first `ply` moves of the game
+    pub fn replay(&self, ply: usize) -> Result<StrategoState, RecordParseError> {
+        let mut pos = self.start()?;
+
+        for (ply, mov) in self.moves.iter().take(ply).enumerate() {
+            let mov = pos
+                .parse_move(&mov.to_string())
+                .map_err(|error| RecordParseError::Move { ply, error })?;
+
+            pos.make(mov);
+        }
+
+        Ok(pos)
+    }
+}
+
+fn parse_header(line: &str) -> Result<(String, String), RecordParseError> {
+    let invalid = || RecordParseError::InvalidHeader(line.to_string());
+
+    let header = line
+        .strip_prefix('[')
+        .and_then(|header| header.strip_suffix(']'))
+        .ok_or_else(invalid)?;
+    let (name, value) = header.split_once(' ').ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Current date in the format `YYYY.MM.DD`
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    // Civil date from the days since 1970-01-01, see
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (seconds / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(rules: Rules) -> GameRecord {
+        let deployments = [
+            "BD3M1G/F5SD/1BCC4".to_string(),
+            "d2f4/bbg4c/1m3dsc".to_string(),
+        ];
+
+        let mut record = GameRecord::new("red", "blue", deployments);
+        record.rules = rules;
+        record
+    }
+
+    #[test]
+    fn move_limit_round_trip() {
+        let rules = Rules {
+            attacker_wins_ties: true,
+            limit: MoveLimit::Total(150),
+            ..Rules::default()
+        };
+        let written = record(rules).to_string();
+
+        assert_eq!(written.matches("MoveLimit").count(), 1);
+        assert!(!written.contains("ply-limit"));
+
+        let parsed: GameRecord = written.parse().unwrap();
+        assert_eq!(parsed.rules, rules);
+        assert_eq!(parsed.start().unwrap().rules().limit, MoveLimit::Total(150));
+    }
+
+    #[test]
+    fn move_limit_header_applies_without_rules() {
+        let written = record(Rules::default())
+            .to_string()
+            .replace("[RedDeployment", "[MoveLimit \"150\"]\n[RedDeployment");
+
+        let parsed: GameRecord = written.parse().unwrap();
+        assert_eq!(parsed.start().unwrap().rules().limit, MoveLimit::Total(150));
+    }
+
+    #[test]
+    fn contradicting_move_limit() {
+        let written = record(Rules {
+            limit: MoveLimit::NoCapture(50),
+            ..Rules::default()
+        })
+        .to_string()
+        .replace("[RedDeployment", "[MoveLimit \"150\"]\n[RedDeployment");
+
+        assert!(written.parse::<GameRecord>().is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use super::{
+    error::RecordParseError,
+    util::{square, Piece},
+    GameRecord, Position,
+};
+
+/// What is drawn on a square besides the board itself
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Square {
+    Empty,
+    Lake,
+    Piece { side: usize, piece: usize },
+}
+
+/// Highlight of the squares of the last move
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Highlight {
+    None,
+    Moved,
+    Attacked,
+}
+
+impl Position {
+    const SQUARE_SIZE: usize = 40;
+    const MARGIN: usize = 20;
+
+    /// Board with colored pieces for terminals supporting ANSI escape codes
+    ///
+    /// Red and blue pieces are drawn in their color, unknown pieces as `?`, lakes in cyan and
+    /// the squares of the last move on yellow or on red, if the move was an attack.
+    pub fn ansi(&self) -> String {
+        const RESET: &str = "\x1b[0m";
+
+        let (files, ranks) = (self.variant().files(), self.variant().ranks());
+        let mut board = String::new();
+
+        for rank in (0..ranks).rev() {
+            board.push_str(&format!("{:>2} ", rank + 1));
+
+            for file in 0..files {
+                let sq = square(file as u8, rank as u8);
+
+                let background = match self.highlight(sq) {
+                    Highlight::None => "",
+                    Highlight::Moved => "\x1b[43m",
+                    Highlight::Attacked => "\x1b[41m",
+                };
+
+                let cell = match self.contents(sq) {
+                    Square::Empty => " . ".to_string(),
+                    Square::Lake => "\x1b[36m ~ ".to_string(),
+                    Square::Piece { side, piece } => {
+                        let color = ["\x1b[1;31m", "\x1b[1;34m"][side];
+                        let symbol = if piece == Piece::UNKNOWN {
+                            '?'
+                        } else {
+                            Position::symbol(side, piece)
+                        };
+
+                        format!("{color} {symbol} ")
+                    }
+                };
+
+                board.push_str(&format!("{background}{cell}{RESET}"));
+            }
+
+            board.push('\n');
+        }
+
+        let files = (b'a'..).take(files).map(|c| format!(" {} ", c as char));
+        board.push_str(&format!("   {}", files.collect::<String>().trim_end()));
+
+        board
+    }
+
+    /// Standalone SVG image of the board for reports and figures
+    pub fn svg(&self) -> String {
+        let (width, height) = self.svg_size();
+        let board = self.svg_board(0, 0);
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             font-family=\"sans-serif\">\n{board}</svg>\n"
+        )
+    }
+
+    /// Width and height of the board including the coordinates
+    fn svg_size(&self) -> (usize, usize) {
+        let (files, ranks) = (self.variant().files(), self.variant().ranks());
+
+        (
+            files * Position::SQUARE_SIZE + Position::MARGIN,
+            ranks * Position::SQUARE_SIZE + Position::MARGIN,
+        )
+    }
+
+    /// Board as SVG group, whose top left corner is at `x` and `y`
+    fn svg_board(&self, x: usize, y: usize) -> String {
+        const SIZE: usize = Position::SQUARE_SIZE;
+
+        let (files, ranks) = (self.variant().files(), self.variant().ranks());
+        let mut svg = format!("<g transform=\"translate({x},{y})\">\n");
+
+        for rank in 0..ranks {
+            for file in 0..files {
+                let sq = square(file as u8, rank as u8);
+                let (left, top) = (Position::MARGIN + file * SIZE, (ranks - 1 - rank) * SIZE);
+
+                let fill = match (self.contents(sq), self.highlight(sq)) {
+                    (Square::Lake, _) => "#7fb3d5",
+                    (_, Highlight::Moved) => "#f7dc6f",
+                    (_, Highlight::Attacked) => "#f1948a",
+                    _ if (file + rank) % 2 == 0 => "#d5c4a1",
+                    _ => "#ebdbb2",
+                };
+
+                svg.push_str(&format!(
+                    "<rect x=\"{left}\" y=\"{top}\" width=\"{SIZE}\" height=\"{SIZE}\" \
+                     fill=\"{fill}\"/>\n"
+                ));
+
+                if let Square::Piece { side, piece } = self.contents(sq) {
+                    let color = ["#c0392b", "#2e5fa8"][side];
+                    let label = if piece == Piece::UNKNOWN {
+                        "?".to_string()
+                    } else {
+                        Piece::rank(piece).to_uppercase()
+                    };
+
+                    svg.push_str(&format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"4\" \
+                         fill=\"{color}\"/>\n",
+                        left + 4,
+                        top + 4,
+                        SIZE - 8,
+                        SIZE - 8
+                    ));
+                    svg.push_str(&format!(
+                        "<text x=\"{}\" y=\"{}\" font-size=\"16\" font-weight=\"bold\" \
+                         fill=\"white\" text-anchor=\"middle\">{label}</text>\n",
+                        left + SIZE / 2,
+                        top + SIZE / 2 + 6
+                    ));
+                }
+            }
+
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\">{}</text>\n",
+                Position::MARGIN / 2,
+                (ranks - 1 - rank) * SIZE + SIZE / 2 + 4,
+                rank + 1
+            ));
+        }
+
+        for file in 0..files {
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\">{}</text>\n",
+                Position::MARGIN + file * SIZE + SIZE / 2,
+                ranks * SIZE + 14,
+                (b'a' + file as u8) as char
+            ));
+        }
+
+        svg.push_str("</g>\n");
+        svg
+    }
+
+    fn contents(&self, sq: u8) -> Square {
+        let bb = 1u128 << sq;
+
+        if (self.variant().lakes() & bb) != 0 {
+            Square::Lake
+        } else if ((self.get(0) | self.get(1)) & bb) == 0 {
+            Square::Empty
+        } else {
+            Square::Piece {
+                side: usize::from((self.get(0) & bb) == 0),
+                piece: self.piece(sq),
+            }
+        }
+    }
+
+    fn highlight(&self, sq: u8) -> Highlight {
+        match self.last_move() {
+            Some((_, to)) if to == sq && self.attacker().is_some() => Highlight::Attacked,
+            Some((from, to)) if from == sq || to == sq => Highlight::Moved,
+            _ => Highlight::None,
+        }
+    }
+}
+
+impl GameRecord {
+    /// SVG image of all positions of the game in rows of `columns` boards, each captioned
+    /// with the move leading to it
+    pub fn svg(&self, columns: usize) -> Result<String, RecordParseError> {
+        const CAPTION: usize = 24;
+
+        let mut pos = self.start()?;
+        let (width, height) = pos.board().svg_size();
+        let (width, height) = (width + Position::MARGIN, height + CAPTION);
+
+        let mut boards = Vec::with_capacity(self.moves.len() + 1);
+        boards.push(("start".to_string(), pos.board()));
+
+        for (ply, mov) in self.moves.iter().enumerate() {
+            let mov = pos
+                .parse_move(&mov.to_string())
+                .map_err(|error| RecordParseError::Move { ply, error })?;
+            pos.make(mov);
+
+            let side = ["red", "blue"][ply % 2];
+            boards.push((format!("{}. {side} {mov}", ply + 1), pos.board()));
+        }
+
+        let columns = columns.clamp(1, boards.len());
+        let rows = boards.len().div_ceil(columns);
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+             font-family=\"sans-serif\">\n",
+            columns * width,
+            rows * height
+        );
+
+        for (i, (caption, board)) in boards.iter().enumerate() {
+            let (x, y) = ((i % columns) * width, (i / columns) * height);
+
+            svg.push_str(&board.svg_board(x, y));
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-size=\"14\" text-anchor=\"middle\">{caption}</text>\n",
+                x + (width - Position::MARGIN) / 2 + Position::MARGIN / 2,
+                y + height - 6
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+}
@@ -0,0 +1,121 @@
+use super::Piece;
+use bincode::{Decode, Encode};
+use std::cmp::Ordering;
+
+/// Draw condition based on the number of moves
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Encode, Decode)]
+pub enum MoveLimit {
+    /// Games are never drawn because of their length
+    #[default]
+    None,
+    /// Game is drawn after the given number of plies
+    Total(usize),
+    /// Game is drawn after the given number of plies without a capture
+    NoCapture(usize),
+}
+
+impl MoveLimit {
+    /// Whether the game is drawn after `plies` plies with the last `half` plies being quiet
+    pub fn reached(&self, plies: usize, half: usize) -> bool {
+        match *self {
+            MoveLimit::None => false,
+            MoveLimit::Total(limit) => plies >= limit,
+            MoveLimit::NoCapture(limit) => half >= limit,
+        }
+    }
+}
+
+/// Rules, which differ between the communities, the default follows the ISF rules
+///
+/// The notation lists all deviations from the default separated by spaces, i.e.
+/// `attacker-wins-ties`, `spy-defends`, `one-time-bombs`, `no-scout-strikes`,
+/// `ply-limit=<plies>` and `capture-limit=<plies>`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Encode, Decode)]
+pub struct Rules {
+    /// Attacker wins against a piece of the same rank instead of removing both pieces
+    pub attacker_wins_ties: bool,
+    /// Spy also defeats the marshal when the marshal attacks
+    pub spy_defends: bool,
+    /// Bomb is removed together with the first piece, which attacks it
+    pub one_time_bombs: bool,
+    /// Scout can move and capture in the same turn
+    pub scout_strikes: bool,
+    pub limit: MoveLimit,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            attacker_wins_ties: false,
+            spy_defends: false,
+            one_time_bombs: false,
+            scout_strikes: true,
+            limit: MoveLimit::None,
+        }
+    }
+}
+
+impl std::fmt::Display for Rules {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut rules = Vec::new();
+
+        if self.attacker_wins_ties {
+            rules.push("attacker-wins-ties".to_string());
+        }
+
+        if self.spy_defends {
+            rules.push("spy-defends".to_string());
+        }
+
+        if self.one_time_bombs {
+            rules.push("one-time-bombs".to_string());
+        }
+
+        if !self.scout_strikes {
+            rules.push("no-scout-strikes".to_string());
+        }
+
+        match self.limit {
+            MoveLimit::None => {}
+            MoveLimit::Total(plies) => rules.push(format!("ply-limit={plies}")),
+            MoveLimit::NoCapture(plies) => rules.push(format!("capture-limit={plies}")),
+        }
+
+        write!(f, "{}", rules.join(" "))
+    }
+}
+
+impl Rules {
+    pub fn parse(notation: &str) -> Option<Self> {
+        let mut rules = Rules::default();
+
+        for rule in notation.split_ascii_whitespace() {
+            match rule.split_once('=') {
+                None if rule == "attacker-wins-ties" => rules.attacker_wins_ties = true,
+                None if rule == "spy-defends" => rules.spy_defends = true,
+                None if rule == "one-time-bombs" => rules.one_time_bombs = true,
+                None if rule == "no-scout-strikes" => rules.scout_strikes = false,
+                Some(("ply-limit", plies)) => rules.limit = MoveLimit::Total(plies.parse().ok()?),
+                Some(("capture-limit", plies)) => {
+                    rules.limit = MoveLimit::NoCapture(plies.parse().ok()?)
+                }
+                _ => return None,
+            }
+        }
+
+        Some(rules)
+    }
+
+    /// Outcome of an attack, i.e. `Greater` if the attacker wins, `Less` if the defender
+    /// wins and `Equal` if both pieces are removed
+    pub fn combat(&self, attacker: usize, defender: usize) -> Ordering {
+        match (attacker, defender) {
+            // Spy can capture marshal or miner can defuse bomb
+            (Piece::SPY, Piece::MARSHAL) | (Piece::MINER, Piece::BOMB) => Ordering::Greater,
+            (Piece::MARSHAL, Piece::SPY) if self.spy_defends => Ordering::Less,
+            (_, Piece::BOMB) if self.one_time_bombs => Ordering::Equal,
+            _ if attacker == defender && self.attacker_wins_ties => Ordering::Greater,
+            _ => attacker.cmp(&defender),
+        }
+    }
+}
@@ -22,6 +22,69 @@ macro_rules! bitboard_loop {
     };
 }
 
+/// Stride of the board layout, which is shared by all variants
+pub const WIDTH: u8 = 10;
+
+pub fn square(file: u8, rank: u8) -> u8 {
+    rank * WIDTH + file
+}
+
+pub fn file(sq: u8) -> u8 {
+    sq % WIDTH
+}
+
+pub fn rank(sq: u8) -> u8 {
+    sq / WIDTH
+}
+
+/// Notation of a square like `a10`
+pub fn square_notation(sq: u8) -> String {
+    format!("{}{}", (b'a' + file(sq)) as char, rank(sq) + 1)
+}
+
+/// Parses a square like `a10` from the start of `notation` and returns the remainder
+pub fn parse_square(notation: &str) -> Option<(u8, &str)> {
+    let mut chars = notation.char_indices();
+
+    let file = match chars.next() {
+        Some((_, c)) if c.is_ascii_lowercase() => c as u8 - b'a',
+        _ => return None,
+    };
+
+    let end = chars
+        .find(|(_, c)| !c.is_ascii_digit())
+        .map_or(notation.len(), |(i, _)| i);
+    let rank = notation[1..end].parse::<u8>().ok()?;
+
+    if file >= WIDTH || rank == 0 || rank > WIDTH {
+        return None;
+    }
+
+    Some((square(file, rank - 1), &notation[end..]))
+}
+
+/// Converts bitboard from board layout to the 8x8 layout of Quick Battle
+pub fn compact(bb: u128) -> u64 {
+    let mut compact = 0u64;
+
+    for rank in 0..8 {
+        compact |= (((bb >> (rank * WIDTH as usize)) & 0xFF) as u64) << (rank * 8);
+    }
+
+    compact
+}
+
+/// Converts bitboard from the 8x8 layout of Quick Battle to board layout
+pub fn expand(bb: u64) -> u128 {
+    let mut expand = 0u128;
+
+    for rank in 0..8 {
+        expand |= (((bb >> (rank * 8)) & 0xFF) as u128) << (rank * WIDTH as usize);
+    }
+
+    expand
+}
+
 pub fn flip_bb(bb: u64) -> u64 {
     const K1: u64 = 0x00FF00FF00FF00FF;
     const K2: u64 = 0x0000FFFF0000FFFF;
@@ -40,10 +103,15 @@ c_enum!(Piece {
     SPY: usize = 3,
     SCOUT: usize = 4,
     MINER: usize = 5,
-    GENERAL: usize = 6,
-    MARSHAL: usize = 7,
-    UNKNOWN: usize = 8,
-    BOMB: usize = 9,
+    SERGEANT: usize = 6,
+    LIEUTENANT: usize = 7,
+    CAPTAIN: usize = 8,
+    MAJOR: usize = 9,
+    COLONEL: usize = 10,
+    GENERAL: usize = 11,
+    MARSHAL: usize = 12,
+    UNKNOWN: usize = 13,
+    BOMB: usize = 14,
 });
 
 impl Piece {
@@ -62,6 +130,11 @@ impl Piece {
             Piece::SPY => "1",
             Piece::SCOUT => "2",
             Piece::MINER => "3",
+            Piece::SERGEANT => "4",
+            Piece::LIEUTENANT => "5",
+            Piece::CAPTAIN => "6",
+            Piece::MAJOR => "7",
+            Piece::COLONEL => "8",
             Piece::GENERAL => "9",
             Piece::MARSHAL => "10",
             Piece::BOMB => "b",
@@ -73,6 +146,13 @@ impl Piece {
         }
         .to_string()
     }
+
+    /// Inverse of `rank`, i.e. the piece denoted by the rank
+    pub fn from_rank(rank: &str) -> Option<usize> {
+        (Piece::FLAG..=Piece::BOMB)
+            .filter(|&piece| piece != Piece::UNKNOWN)
+            .find(|&piece| Piece::rank(piece) == rank)
+    }
 }
 
 c_enum!(Flag {
@@ -84,9 +164,9 @@ c_enum!(Flag {
 pub struct Zobrist(());
 
 impl Zobrist {
-    const HASHES: [u64; 2 * 8 * 64] = {
+    const HASHES: [u64; 2 * 13 * 100] = {
         let mut seed: u64 = 1070372;
-        let mut hashes = [0u64; 2 * 8 * 64];
+        let mut hashes = [0u64; 2 * 13 * 100];
 
         let mut i = 0;
         while i < hashes.len() {
@@ -102,6 +182,6 @@ impl Zobrist {
     };
 
     pub fn get(stm: usize, sq: usize, piece: usize) -> u64 {
-        Zobrist::HASHES[stm * 8 * 64 + (piece - 2) * 64 + sq]
+        Zobrist::HASHES[stm * 13 * 100 + (piece - 2) * 100 + sq]
     }
 }
@@ -0,0 +1,181 @@
+use super::{util::WIDTH, Army};
+use crate::bitboard_loop;
+use bincode::{Decode, Encode};
+
+/// Board geometry of the supported game types
+///
+/// Both variants share the same board layout with a stride of `WIDTH`,
+/// Quick Battle only uses the lower-left 8x8 squares of it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Encode, Decode)]
+pub enum Variant {
+    #[default]
+    QuickBattle,
+    Classic,
+}
+
+impl Variant {
+    const QUICK_BATTLE_LAKES: u128 = mask(&[(2, 3), (5, 3), (2, 4), (5, 4)]);
+    const CLASSIC_LAKES: u128 = mask(&[
+        (2, 4),
+        (3, 4),
+        (6, 4),
+        (7, 4),
+        (2, 5),
+        (3, 5),
+        (6, 5),
+        (7, 5),
+    ]);
+
+    const QUICK_BATTLE_SQUARES: u128 = board(8);
+    const CLASSIC_SQUARES: u128 = board(10);
+
+    pub fn from_ranks(ranks: usize) -> Option<Self> {
+        match ranks {
+            8 => Some(Variant::QuickBattle),
+            10 => Some(Variant::Classic),
+            _ => None,
+        }
+    }
+
+    /// Variant, whose deployment has as many ranks as `deployment`
+    pub fn from_deployment(deployment: &str) -> Option<Self> {
+        let ranks = deployment.split('/').count();
+
+        [Variant::QuickBattle, Variant::Classic]
+            .into_iter()
+            .find(|variant| variant.deployment_ranks() == ranks)
+    }
+
+    /// Square mirrored between the left and right side of the board
+    pub fn mirror(&self, sq: u8) -> u8 {
+        const QUICK_BATTLE: [u8; 100] = mirrored(8);
+        const CLASSIC: [u8; 100] = mirrored(10);
+
+        match self {
+            Variant::QuickBattle => QUICK_BATTLE[sq as usize],
+            Variant::Classic => CLASSIC[sq as usize],
+        }
+    }
+
+    /// Bitboard mirrored between the left and right side of the board
+    pub fn mirror_bb(&self, bb: u128) -> u128 {
+        let mut bb = bb;
+        let mut mirrored = 0u128;
+        bitboard_loop!(bb, sq, mirrored |= 1u128 << self.mirror(sq));
+
+        mirrored
+    }
+
+    pub fn files(&self) -> usize {
+        match self {
+            Variant::QuickBattle => 8,
+            Variant::Classic => 10,
+        }
+    }
+
+    pub fn ranks(&self) -> usize {
+        self.files()
+    }
+
+    /// Number of ranks each side deploys its pieces in
+    pub fn deployment_ranks(&self) -> usize {
+        match self {
+            Variant::QuickBattle => 3,
+            Variant::Classic => 4,
+        }
+    }
+
+    /// Army, which is played on the board by default
+    pub fn army(&self) -> Army {
+        match self {
+            Variant::QuickBattle => Army::QUICK_BATTLE,
+            Variant::Classic => Army::CLASSIC,
+        }
+    }
+
+    /// Notation of the start position with red to move, both deployments are given in
+    /// the notation of their ranks from top to bottom
+    pub fn start(&self, red: &str, blue: &str) -> String {
+        let empty = vec![self.files().to_string(); self.ranks() - 2 * self.deployment_ranks()];
+
+        format!("{blue}/{}/{red} r", empty.join("/"))
+    }
+
+    /// Squares of the start rows of one side
+    pub fn start_rows(&self, side: usize) -> u128 {
+        let rows = self.deployment_ranks();
+        let red = (1u128 << (rows * WIDTH as usize)) - 1;
+
+        let shift = if side == 0 {
+            0
+        } else {
+            (self.ranks() - rows) * WIDTH as usize
+        };
+
+        (red << shift) & self.squares()
+    }
+
+    pub fn lakes(&self) -> u128 {
+        match self {
+            Variant::QuickBattle => Variant::QUICK_BATTLE_LAKES,
+            Variant::Classic => Variant::CLASSIC_LAKES,
+        }
+    }
+
+    /// All squares on the board, including the lakes
+    pub fn squares(&self) -> u128 {
+        match self {
+            Variant::QuickBattle => Variant::QUICK_BATTLE_SQUARES,
+            Variant::Classic => Variant::CLASSIC_SQUARES,
+        }
+    }
+
+    /// Squares no piece can ever enter, i.e. lakes and squares outside the board
+    pub fn blocked(&self) -> u128 {
+        self.lakes() | !self.squares()
+    }
+}
+
+const fn board(size: u8) -> u128 {
+    let mut bb = 0u128;
+
+    let mut rank = 0;
+    while rank < size {
+        bb |= ((1u128 << size) - 1) << (rank * WIDTH);
+        rank += 1;
+    }
+
+    bb
+}
+
+const fn mask(squares: &[(u8, u8)]) -> u128 {
+    let mut bb = 0u128;
+
+    let mut i = 0;
+    while i < squares.len() {
+        let (file, rank) = squares[i];
+        bb |= 1u128 << (rank * WIDTH + file);
+        i += 1;
+    }
+
+    bb
+}
+
+/// Mirrored square of each square of a board with `files` files, other squares are kept
+const fn mirrored(files: u8) -> [u8; 100] {
+    let mut squares = [0u8; 100];
+
+    let mut sq = 0;
+    while sq < 100 {
+        let (file, rank) = (sq % WIDTH, sq / WIDTH);
+
+        squares[sq as usize] = if file < files {
+            rank * WIDTH + files - 1 - file
+        } else {
+            sq
+        };
+        sq += 1;
+    }
+
+    squares
+}
@@ -1,11 +1,13 @@
 use crate::{
-    stratego::{GameState, StrategoState},
+    mcts::Clock,
+    stratego::{Army, GameRecord, GameState, MoveLimit, Outcome, Rules},
     Algorithm,
 };
 use engine::Engine;
 use ordered_float::OrderedFloat;
 use rating::Ranking;
 use schedule::Schedule;
+use std::time::Instant;
 
 pub mod engine;
 mod rating;
@@ -14,6 +16,11 @@ mod schedule;
 pub struct Tournament {
     engines: Vec<Engine>,
     results: Vec<Ranking>,
+    records: Vec<GameRecord>,
+    rules: Rules,
+    army: Army,
+    clock: Option<Clock>,
+    ponder: bool,
     limit: usize,
 }
 
@@ -22,6 +29,14 @@ impl Tournament {
         Self {
             engines: Vec::new(),
             results: Vec::new(),
+            records: Vec::new(),
+            rules: Rules {
+                limit: MoveLimit::Total(limit),
+                ..Rules::default()
+            },
+            army: Army::QUICK_BATTLE,
+            clock: None,
+            ponder: false,
             limit,
         }
     }
@@ -31,6 +46,32 @@ impl Tournament {
         self.engines.push(Engine::new(name, algorithm, cheating));
     }
 
+    /// Rules of all following games, which replace the move limit of the tournament
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+    }
+
+    /// Army, which both engines deploy in all following games
+    pub fn set_army(&mut self, army: Army) {
+        self.army = army;
+    }
+
+    /// Time control of all following games, in which both sides start with `clock` and a side,
+    /// whose time runs out, loses
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+
+    /// Whether engines search on the opponent's time in all following games
+    pub fn set_ponder(&mut self, ponder: bool) {
+        self.ponder = ponder;
+    }
+
+    /// Records of all games played so far
+    pub fn records(&self) -> &[GameRecord] {
+        &self.records
+    }
+
     pub fn run(&mut self, rounds: usize) {
         assert!(self.engines.len() >= 2);
 
@@ -71,24 +112,32 @@ impl Tournament {
         branching: &mut Vec<usize>,
         length: &mut Vec<usize>,
     ) {
-        let mut history = Vec::new();
-
         let deployments = self.deployment(i, j);
-        let pos_str = format!("{}/8/8/{} r", deployments.1, deployments.0);
+        let mut record = GameRecord::new(
+            self.engines[i].name(),
+            self.engines[j].name(),
+            [deployments.0, deployments.1],
+        );
+        record.rules = self.rules;
+        record.army = self.army;
 
-        let winner = self.game_loop(i, j, &mut history, &pos_str, rounds, branching);
+        let winner = self.game_loop(i, j, &mut record, rounds, branching);
+        self.engines[i].stop();
+        self.engines[j].stop();
         self.results[i].update(winner[0]);
         self.results[j].update(winner[1]);
 
-        length[index] = history.len();
+        length[index] = record.moves.len();
 
         println!(
             "info game {}/{} pos {} moves {}",
             index,
             limit,
-            pos_str,
-            history.len()
+            record.start().unwrap(),
+            record.moves.len()
         );
+
+        self.records.push(record);
     }
 
     fn result(&mut self) -> String {
@@ -116,63 +165,96 @@ impl Tournament {
         &mut self,
         i: usize,
         j: usize,
-        moves: &mut Vec<String>,
-        pos_str: &str,
+        record: &mut GameRecord,
         rounds: &mut Vec<usize>,
         branching: &mut Vec<usize>,
     ) -> [f32; 2] {
         let indices = [i, j];
 
-        let mut pos = StrategoState::from(&pos_str);
+        let mut pos = record.start().unwrap();
+        let mut moves = Vec::new();
 
         let mut ply = 0;
         let mut stm = 0;
+        let mut clocks = [self.clock; 2];
+        let mut observations = [pos.observe(0), pos.observe(1)];
         while !pos.game_over() {
             let gen = pos.gen();
 
-            if gen.len() == 0 {
-                pos.set_game_state(GameState::Loss);
-                break;
-            }
+            let observation = if self.engines[indices[stm]].cheating() {
+                pos.observe_all()
+            } else {
+                observations[stm].update(&pos);
+                observations[stm].clone()
+            };
 
-            if moves.len() > self.limit {
-                pos.set_game_state(GameState::Draw);
-                break;
+            if let Some(clock) = clocks[stm] {
+                self.engines[indices[stm]].set_clock(clock);
             }
 
-            let player_pos = if self.engines[indices[stm]].cheating() {
-                pos.clone()
-            } else {
-                pos.anonymize(stm ^ 1)
-            };
+            let start = Instant::now();
+            let mov = self.engines[indices[stm]].go(&observation);
+
+            if let Some(clock) = clocks[stm] {
+                clocks[stm] = clock.charge(start.elapsed());
 
-            let mov = self.engines[indices[stm]].go(player_pos);
+                if clocks[stm].is_none() {
+                    record.result = Outcome::winner(stm ^ 1);
+                    record.termination = "time forfeit".to_string();
+
+                    let mut result = [0.0, 0.0];
+                    result[stm ^ 1] = 1.0;
+                    return result;
+                }
+            }
             moves.push(format!("{}", mov));
 
             #[cfg(feature = "info")]
             println!("info move {} stm {} moves {:?}", mov, stm, moves);
 
-            let mov = gen.iter().find(|m| format!("{}", m) == format!("{}", mov));
-            if mov.is_none() {
-                println!("{}", pos);
-                println!("{:?}", moves);
+            let mov = match pos.parse_move(&format!("{}", mov)) {
+                Ok(mov) => mov,
+                Err(err) => {
+                    println!("{}", pos);
+                    println!("{:?} {}", moves, err);
+
+                    // TODO: unreachable!();
+                    record.result = Outcome::Draw;
+                    record.termination = "illegal move".to_string();
+                    return [0.5, 0.5];
+                }
+            };
 
-                // TODO: unreachable!();
-                return [0.5, 0.5];
+            if ply < rounds.len() {
+                rounds[ply] += 1;
+                branching[ply] += gen.len();
             }
 
-            rounds[ply] += 1;
-            branching[ply] += gen.len();
+            record.moves.push(mov);
+            pos.make(mov);
+
+            if self.ponder && !pos.game_over() {
+                let engine = &mut self.engines[indices[stm]];
+                let observation = if engine.cheating() {
+                    pos.observe_all()
+                } else {
+                    observations[stm].update(&pos);
+                    observations[stm].clone()
+                };
+
+                engine.ponder(&observation);
+            }
 
             stm ^= 1;
             ply += 1;
-
-            let mov = mov.unwrap();
-            pos.make(mov);
         }
 
+        let game_result = pos.result().unwrap();
+        record.result = Outcome::from(&pos);
+        record.termination = game_result.termination.to_string();
+
         let mut result = [0.0, 0.0];
-        match pos.game_state() {
+        match game_result.state {
             GameState::Win => result[stm] = 1.0,
             GameState::Draw => result = [0.5, 0.5],
             GameState::Loss => result[stm ^ 1] = 1.0,
@@ -187,13 +269,13 @@ impl Tournament {
     fn deployment(&mut self, i: usize, j: usize) -> (String, String) {
         (
             self.engines[i]
-                .deployment()
+                .deployment(&self.army)
                 .to_ascii_uppercase()
                 .split('/')
                 .rev()
                 .collect::<Vec<_>>()
                 .join("/"),
-            self.engines[j].deployment().to_ascii_lowercase(),
+            self.engines[j].deployment(&self.army).to_ascii_lowercase(),
         )
     }
 }
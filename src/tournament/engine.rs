@@ -1,11 +1,12 @@
 use crate::{
-    stratego::{Move, StrategoState},
+    mcts::{Clock, Limits, Pondering},
+    stratego::{Army, Move, Observation},
     Algorithm,
 };
 
 pub struct Engine {
     name: String,
-    algorithm: Algorithm,
+    algorithm: Pondering<Algorithm>,
     cheating: bool,
 }
 
@@ -13,7 +14,7 @@ impl Engine {
     pub fn new(name: &str, algorithm: Algorithm, cheating: bool) -> Self {
         Self {
             name: name.to_string(),
-            algorithm,
+            algorithm: Pondering::new(algorithm),
             cheating,
         }
     }
@@ -26,11 +27,32 @@ impl Engine {
         self.cheating
     }
 
-    pub fn go(&mut self, pos: StrategoState) -> Move {
-        Algorithm::go(&mut self.algorithm, &pos)
+    pub fn go(&mut self, pos: &Observation) -> Move {
+        Algorithm::go(self.algorithm.get(), pos)
     }
 
-    pub fn deployment(&mut self) -> String {
-        Algorithm::deployment(&mut self.algorithm)
+    /// Searches on while the opponent thinks about its reply to our move in `pos`
+    pub fn ponder(&mut self, pos: &Observation) {
+        self.algorithm.start(pos);
+    }
+
+    pub fn stop(&mut self) {
+        self.algorithm.stop();
+    }
+
+    /// Game clock of the following searches of the engine
+    pub fn set_clock(&mut self, clock: Clock) {
+        let algorithm = self.algorithm.get();
+
+        if let Some(limits) = algorithm.limits() {
+            algorithm.set_limits(Limits {
+                clock: Some(clock),
+                ..limits
+            });
+        }
+    }
+
+    pub fn deployment(&mut self, army: &Army) -> String {
+        Algorithm::deployment(self.algorithm.get(), army)
     }
 }
@@ -1,14 +1,19 @@
 use crate::{
     bitboard_loop,
     deployment::heuristic::evaluate_bb,
-    stratego::{chebyshev, flip_bb, orthogonal, Piece, Position, StrategoState},
+    stratego::{chebyshev, compact, flip_bb, orthogonal, Piece, StrategoState, Variant},
 };
 
-const VALUES: [f32; 8] = [
+const VALUES: [f32; 13] = [
     10000.0, // Flag
     200.0,   // Spy
     25.0,    // Miner
     30.0,    // Scout
+    20.0,    // Sergeant
+    40.0,    // Lieutenant
+    70.0,    // Captain
+    100.0,   // Major
+    150.0,   // Colonel
     200.0,   // General
     400.0,   // Marshal
     0.0,     // Unknown
@@ -20,17 +25,23 @@ pub fn heuristic(pos: &mut StrategoState, scaling: f32) -> f32 {
 }
 
 pub fn evaluate(pos: &mut StrategoState) -> f32 {
-    let board = pos.board();
+    // Positional terms are tuned for Quick Battle only
+    if pos.board().variant() != Variant::QuickBattle {
+        return material(pos);
+    }
+
+    let army = pos.board().army();
+    let board = pos.board().bb().map(compact);
     let info = pos.information();
     let stm = pos.stm() as usize;
 
     let mut sum = 0.0;
     for side in [stm, stm ^ 1] {
-        let us = board.get(side);
-        let them = board.get(side ^ 1);
-        let unknown = info.get(side);
+        let us = board[side];
+        let them = board[side ^ 1];
+        let unknown = compact(info.get(side));
 
-        let flag = (board.get(Piece::FLAG) & them).trailing_zeros() as usize;
+        let flag = (board[Piece::FLAG] & them).trailing_zeros() as usize;
         if flag == 0 {
             sum -= VALUES[0];
 
@@ -45,7 +56,7 @@ pub fn evaluate(pos: &mut StrategoState) -> f32 {
                 continue;
             }
 
-            let mut mask = board.get(piece) & us;
+            let mut mask = board[piece] & us;
             let count = mask.count_ones();
 
             let mut value = VALUES[piece - 2];
@@ -54,21 +65,23 @@ pub fn evaluate(pos: &mut StrategoState) -> f32 {
                 value = max * 0.5;
             }
 
-            if piece == Piece::MARSHAL && (board.get(Piece::SPY) & them) != 0 {
+            if piece == Piece::MARSHAL && (board[Piece::SPY] & them) != 0 {
                 value *= 0.5;
             }
 
+            // Last remaining piece of a rank, which the army has several of
             if (piece == Piece::SCOUT || piece == Piece::MINER || piece == Piece::BOMB)
                 && count == 1
+                && army.count(piece) > 1
             {
                 value *= 1.5;
             }
 
-            if count > (them & board.get(piece)).count_ones() {
+            if count > (them & board[piece]).count_ones() {
                 value *= 1.5;
             }
 
-            if piece == Piece::SPY && (them & board.get(Piece::MARSHAL)) == 0 {
+            if piece == Piece::SPY && (them & board[Piece::MARSHAL]) == 0 {
                 value /= 5.0;
             }
 
@@ -93,23 +106,14 @@ pub fn evaluate(pos: &mut StrategoState) -> f32 {
             sum += lower_ranked(&board, side, piece, side == stm);
         }
 
-        let mut bb = [
-            us,
-            0,
-            board.get(Piece::FLAG) & us,
-            board.get(Piece::SPY) & us,
-            board.get(Piece::SCOUT) & us,
-            board.get(Piece::MINER) & us,
-            board.get(Piece::GENERAL) & us,
-            board.get(Piece::MARSHAL) & us,
-            0,
-            board.get(Piece::BOMB) & us,
-        ];
+        sum += unknown_value(pos, side);
+
+        let mut bb = board.map(|bb| bb & us);
+        bb[1] = 0;
+        bb[Piece::UNKNOWN] = 0;
 
         if side == 1 {
-            for i in 0..10 {
-                bb[i] = flip_bb(bb[i]);
-            }
+            bb = bb.map(flip_bb);
         }
 
         sum += 20.0 * evaluate_bb(bb) as f32;
@@ -120,9 +124,9 @@ pub fn evaluate(pos: &mut StrategoState) -> f32 {
     sum
 }
 
-fn lower_ranked(board: &Position, side: usize, piece: usize, bonus: bool) -> f32 {
+fn lower_ranked(board: &[u64; 15], side: usize, piece: usize, bonus: bool) -> f32 {
     let mut score = 0.0;
-    let mut piece_bb = board.get(side) & board.get(piece);
+    let mut piece_bb = board[side] & board[piece];
 
     bitboard_loop!(piece_bb, sq, {
         let orthogonal = orthogonal(sq as usize);
@@ -134,7 +138,7 @@ fn lower_ranked(board: &Position, side: usize, piece: usize, bonus: bool) -> f32
         };
 
         for lower in (Piece::SPY..piece).chain(more) {
-            let lower_bb = board.get(side ^ 1) & board.get(lower);
+            let lower_bb = board[side ^ 1] & board[lower];
             score += (lower_bb & orthogonal).count_ones() as f32
                 * if bonus { VALUES[lower - 2] / 2.0 } else { 5.0 };
         }
@@ -142,3 +146,40 @@ fn lower_ranked(board: &Position, side: usize, piece: usize, bonus: bool) -> f32
 
     score
 }
+
+fn material(pos: &StrategoState) -> f32 {
+    let board = pos.board();
+    let stm = pos.stm() as usize;
+
+    let mut sum = 0.0;
+    for piece in Piece::FLAG..=Piece::BOMB {
+        let us = (board.get(piece) & board.get(stm)).count_ones() as f32;
+        let them = (board.get(piece) & board.get(stm ^ 1)).count_ones() as f32;
+
+        sum += (us - them) * VALUES[piece - 2];
+    }
+
+    sum + unknown_value(pos, stm) - unknown_value(pos, stm ^ 1)
+}
+
+/// Expected value of the unknown pieces of `side` by the beliefs of its opponent
+fn unknown_value(pos: &StrategoState, side: usize) -> f32 {
+    let board = pos.board();
+    let mut unknown = board.get(Piece::UNKNOWN) & board.get(side);
+
+    if unknown == 0 {
+        return 0.0;
+    }
+
+    let beliefs = pos.beliefs(side ^ 1);
+
+    let mut sum = 0.0;
+    bitboard_loop!(unknown, sq, {
+        // Flag is excluded, because its value only marks the end of the game
+        sum += (Piece::SPY..=Piece::BOMB)
+            .map(|piece| beliefs[sq as usize][piece] * VALUES[piece - 2])
+            .sum::<f32>();
+    });
+
+    sum
+}
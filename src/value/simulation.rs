@@ -14,19 +14,12 @@ pub fn simulation_uniform(pos: &mut StrategoState) -> f32 {
     let mut rng = rng();
 
     let stm = pos.stm();
-    while !pos.game_over() {
-        let mov = pos.gen().iter().choose(&mut rng);
-
-        if let Some(mov) = mov {
-            pos.make(mov);
-        } else {
-            // Handle two-squares and more-squares rule
-            pos.set_game_state(GameState::Loss);
-        }
+    while let Some(mov) = pos.gen().iter().choose(&mut rng) {
+        pos.make(mov);
     }
 
     let current = f32::from(stm == pos.stm());
-    match pos.game_state() {
+    match state(pos) {
         GameState::Draw => 0.0,
         GameState::Win => -1.0 + (2.0 * current),
         GameState::Loss => 1.0 + (-2.0 * current),
@@ -40,10 +33,7 @@ pub fn simulation_ordered(pos: &mut StrategoState, weights: &[f32; 5]) -> f32 {
     let stm = pos.stm();
     while !pos.game_over() {
         let moves = pos.gen();
-
         if moves.len() == 0 {
-            // Handle two-squares and more-squares rule
-            pos.set_game_state(GameState::Loss);
             break;
         }
 
@@ -54,7 +44,7 @@ pub fn simulation_ordered(pos: &mut StrategoState, weights: &[f32; 5]) -> f32 {
     }
 
     let current = f32::from(stm == pos.stm());
-    match pos.game_state() {
+    match state(pos) {
         GameState::Draw => 0.0,
         GameState::Win => -1.0 + (2.0 * current),
         GameState::Loss => 1.0 + (-2.0 * current),
@@ -71,18 +61,14 @@ pub fn simulation_cutoff(pos: &mut StrategoState, c: f32, heuristic: Heuristic)
             break;
         }
 
-        let mov = pos.gen().iter().choose(&mut rng);
-
-        if let Some(mov) = mov {
-            pos.make(mov);
-        } else {
-            // Handle two-squares and more-squares rule
-            pos.set_game_state(GameState::Loss);
-        }
+        let Some(mov) = pos.gen().iter().choose(&mut rng) else {
+            break;
+        };
+        pos.make(mov);
     }
 
     let current = f32::from(stm == pos.stm());
-    match pos.game_state() {
+    match state(pos) {
         GameState::Draw => 0.0,
         GameState::Win => -1.0 + (2.0 * current),
         GameState::Loss => 1.0 + (-2.0 * current),
@@ -106,8 +92,6 @@ pub fn simulation_ordered_cutoff(
 
         let moves = pos.gen();
         if moves.len() == 0 {
-            // Handle two-squares and more-squares rule
-            pos.set_game_state(GameState::Loss);
             break;
         }
 
@@ -118,10 +102,50 @@ pub fn simulation_ordered_cutoff(
     }
 
     let current = f32::from(stm == pos.stm());
-    match pos.game_state() {
+    match state(pos) {
         GameState::Draw => 0.0,
         GameState::Win => -1.0 + (2.0 * current),
         GameState::Loss => 1.0 + (-2.0 * current),
         GameState::Ongoing => heuristic(pos) * (-1.0 + 2.0 * current),
     }
 }
+
+/// State at the end of a simulation, in which the side to move loses without generated moves
+///
+/// The game only ends, once no piece can move, but pieces of unknown rank aren't moved, so
+/// a simulation of a state with such pieces can run out of moves before.
+fn state(pos: &StrategoState) -> GameState {
+    match pos.game_state() {
+        GameState::Ongoing if pos.gen().len() == 0 => GameState::Loss,
+        state => state,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn simulations_end_without_moves() {
+        // Blue only has pieces of unknown rank in the observation of red
+        let mut pos = StrategoState::from("d2f4/bbg4c/1m3dsc/8/8/BD3M1G/F5SD/1BCC4 r");
+        pos.make(pos.gen()[0]);
+        let observation = pos.observe(0);
+
+        let heuristic: Heuristic = |_| 0.5;
+        let weights = [1.0; 5];
+        let simulations: [Box<dyn Fn(&mut StrategoState) -> f32>; 4] = [
+            Box::new(simulation_uniform),
+            Box::new(|pos| simulation_ordered(pos, &weights)),
+            Box::new(|pos| simulation_cutoff(pos, 0.0, heuristic)),
+            Box::new(|pos| simulation_ordered_cutoff(pos, &weights, 0.0, heuristic)),
+        ];
+
+        for simulation in simulations {
+            let mut state = observation.state().clone();
+
+            assert!(state.gen().len() == 0 && !state.game_over());
+            assert_eq!(simulation(&mut state), -1.0);
+        }
+    }
+}
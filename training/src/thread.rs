@@ -5,7 +5,7 @@ use stratego::{
     mcts::{Search, MCTS},
     policy::Policy,
     select::Select,
-    stratego::{GameState, StrategoState},
+    stratego::{Army, GameState, MoveLimit, StrategoState},
     value::{heuristic, Network, Value},
 };
 
@@ -47,35 +47,20 @@ impl DatagenThread {
         let mut data = Vec::new();
 
         let mut rng = rng();
-        let mut ply = 0;
 
-        while ply < DatagenThread::RANDOM && !pos.game_over() {
-            ply += 1;
-
-            let mov = pos.gen().iter().choose(&mut rng);
+        while pos.ply() < DatagenThread::RANDOM {
+            let Some(mov) = pos.gen().iter().choose(&mut rng) else {
+                break;
+            };
 
-            if let Some(mov) = mov {
-                pos.make(mov);
-            } else {
-                // Handle two-squares and more-squares rule
-                pos.set_game_state(GameState::Loss);
-            }
+            pos.make(mov);
         }
 
-        ply = 0;
-        while !pos.game_over() {
-            ply += 1;
+        // Moves of the random opening don't count against the limit
+        pos.set_limit(MoveLimit::Total(pos.ply() + DatagenThread::LIMIT));
 
+        while !pos.game_over() {
             let gen = pos.gen();
-            if gen.len() == 0 {
-                pos.set_game_state(GameState::Loss);
-                break;
-            }
-
-            if ply > DatagenThread::LIMIT {
-                pos.set_game_state(GameState::Draw);
-                break;
-            }
 
             let red = pos.features::<0>();
             let blue = pos.features::<1>();
@@ -129,13 +114,16 @@ impl DatagenThread {
     fn deployment(&mut self) -> String {
         let red = self
             .mcts
-            .deployment()
+            .deployment(&Army::QUICK_BATTLE)
             .to_ascii_uppercase()
             .split('/')
             .rev()
             .collect::<Vec<_>>()
             .join("/");
-        let blue = self.mcts.deployment().to_ascii_lowercase();
+        let blue = self
+            .mcts
+            .deployment(&Army::QUICK_BATTLE)
+            .to_ascii_lowercase();
 
         format!("{}/8/8/{} r", blue, red)
     }